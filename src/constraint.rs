@@ -0,0 +1,180 @@
+//! Inline parameter constraints, such as `/orders/:id<u64>`.
+use regex::Regex;
+
+/// A constraint on the value of a route parameter.
+#[derive(Clone, Debug)]
+pub(crate) enum Constraint {
+    Type(ParamType),
+    Regex(Regex),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ParamType {
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    Usize,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    Isize,
+    F32,
+    F64,
+    Bool,
+}
+
+impl ParamType {
+    fn from_name(name: &str) -> Option<Self> {
+        let ty = match name {
+            "u8" => ParamType::U8,
+            "u16" => ParamType::U16,
+            "u32" => ParamType::U32,
+            "u64" => ParamType::U64,
+            "u128" => ParamType::U128,
+            "usize" => ParamType::Usize,
+            "i8" => ParamType::I8,
+            "i16" => ParamType::I16,
+            "i32" => ParamType::I32,
+            "i64" => ParamType::I64,
+            "i128" => ParamType::I128,
+            "isize" => ParamType::Isize,
+            "f32" => ParamType::F32,
+            "f64" => ParamType::F64,
+            "bool" => ParamType::Bool,
+            _ => return None,
+        };
+
+        Some(ty)
+    }
+
+    fn accepts(self, value: &str) -> bool {
+        match self {
+            ParamType::U8 => value.parse::<u8>().is_ok(),
+            ParamType::U16 => value.parse::<u16>().is_ok(),
+            ParamType::U32 => value.parse::<u32>().is_ok(),
+            ParamType::U64 => value.parse::<u64>().is_ok(),
+            ParamType::U128 => value.parse::<u128>().is_ok(),
+            ParamType::Usize => value.parse::<usize>().is_ok(),
+            ParamType::I8 => value.parse::<i8>().is_ok(),
+            ParamType::I16 => value.parse::<i16>().is_ok(),
+            ParamType::I32 => value.parse::<i32>().is_ok(),
+            ParamType::I64 => value.parse::<i64>().is_ok(),
+            ParamType::I128 => value.parse::<i128>().is_ok(),
+            ParamType::Isize => value.parse::<isize>().is_ok(),
+            ParamType::F32 => value.parse::<f32>().is_ok(),
+            ParamType::F64 => value.parse::<f64>().is_ok(),
+            ParamType::Bool => value.parse::<bool>().is_ok(),
+        }
+    }
+}
+
+impl Constraint {
+    /// Returns `true` if the parameter value satisfies the constraint.
+    pub(crate) fn accepts(&self, value: &str) -> bool {
+        match self {
+            Constraint::Type(ty) => ty.accepts(value),
+            Constraint::Regex(regex) => regex.is_match(value),
+        }
+    }
+}
+
+/// Removes the constraints from a route pattern, returning the plain pattern and the
+/// constraint of each constrained parameter.
+///
+/// A constraint is either the name of a primitive type, such as `<u64>` or `<bool>`,
+/// or a regular expression that must match the entire value, such as `<[a-z]+>`.
+pub(crate) fn parse(pattern: &str) -> Result<(String, Vec<(String, Constraint)>), String> {
+    let mut path = String::with_capacity(pattern.len());
+    let mut constraints = Vec::new();
+    let mut rest = pattern;
+
+    while let Some(i) = rest.find(&[':', '*'][..]) {
+        let name_len = rest[i + 1..]
+            .find(&['/', '<'][..])
+            .unwrap_or(rest.len() - i - 1);
+        let end = i + 1 + name_len;
+        path.push_str(&rest[..end]);
+
+        if !rest[end..].starts_with('<') {
+            rest = &rest[end..];
+            continue;
+        }
+
+        let len = closing_bracket(&rest[end..])
+            .ok_or_else(|| format!("unclosed constraint for parameter '{}'", &rest[i..end]))?;
+        let name = &rest[i + 1..end];
+        let source = &rest[end + 1..end + len];
+
+        let constraint = match ParamType::from_name(source) {
+            Some(ty) => Constraint::Type(ty),
+            None => Regex::new(&format!("^(?:{})$", source))
+                .map(Constraint::Regex)
+                .map_err(|err| format!("invalid constraint for parameter '{}': {}", name, err))?,
+        };
+
+        constraints.push((name.to_owned(), constraint));
+        rest = &rest[end + len + 1..];
+    }
+
+    path.push_str(rest);
+    Ok((path, constraints))
+}
+
+/// Returns the index of the `>` closing the `<` at the start of the string.
+fn closing_bracket(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut escaped = false;
+
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let (path, constraints) = parse("/orders/:id<u64>/items/:item").unwrap();
+        assert_eq!(path, "/orders/:id/items/:item");
+        assert_eq!(constraints.len(), 1);
+        assert_eq!(constraints[0].0, "id");
+        assert!(constraints[0].1.accepts("42"));
+        assert!(!constraints[0].1.accepts("abc"));
+
+        let (path, constraints) = parse("/files/:name<[a-z0-9_-]+>").unwrap();
+        assert_eq!(path, "/files/:name");
+        assert!(constraints[0].1.accepts("read_me-2"));
+        assert!(!constraints[0].1.accepts("README"));
+
+        let (path, constraints) = parse("/tags/:tag<(?P<x>a|b)-\\d>/*rest").unwrap();
+        assert_eq!(path, "/tags/:tag/*rest");
+        assert!(constraints[0].1.accepts("a-1"));
+        assert!(!constraints[0].1.accepts("c-1"));
+
+        let (path, constraints) = parse("/users/:id").unwrap();
+        assert_eq!(path, "/users/:id");
+        assert!(constraints.is_empty());
+
+        assert!(parse("/orders/:id<u64").is_err());
+        assert!(parse("/orders/:id<[a-z>").is_err());
+    }
+}
@@ -0,0 +1,416 @@
+//! Deserialization of route parameters with serde.
+use std::error::Error as StdError;
+use std::fmt;
+
+use hyper::{header, Body, Response, StatusCode};
+use serde::de::{self, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::forward_to_deserialize_any;
+
+/// An error that occurred while extracting route parameters.
+///
+/// When returned from a handler, the router answers the request with
+/// `400 Bad Request`.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamsError {
+    /// The parameter was not captured by the route.
+    Missing { key: String },
+    /// The parameter could not be parsed into the requested type.
+    Invalid {
+        key: String,
+        value: String,
+        message: String,
+    },
+    /// The number of parameters does not match the requested type.
+    Length { expected: usize, found: usize },
+    /// A custom error returned by a `Deserialize` implementation.
+    Custom(String),
+}
+
+impl fmt::Display for ParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamsError::Missing { key } => write!(f, "missing route parameter '{}'", key),
+            ParamsError::Invalid {
+                key,
+                value,
+                message,
+            } => write!(
+                f,
+                "invalid value '{}' for route parameter '{}': {}",
+                value, key, message
+            ),
+            ParamsError::Length { expected, found } => write!(
+                f,
+                "expected {} route parameter(s), found {}",
+                expected, found
+            ),
+            ParamsError::Custom(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl StdError for ParamsError {}
+
+impl de::Error for ParamsError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        ParamsError::Custom(msg.to_string())
+    }
+}
+
+impl From<ParamsError> for Response<Body> {
+    fn from(err: ParamsError) -> Self {
+        Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(Body::from(err.to_string()))
+            .unwrap()
+    }
+}
+
+/// Deserializes a list of parameters into a map, struct, tuple or sequence, or a
+/// single parameter into a primitive value.
+pub(crate) struct ParamsDeserializer<'de> {
+    params: &'de [(String, String)],
+}
+
+impl<'de> ParamsDeserializer<'de> {
+    pub(crate) fn new(params: &'de [(String, String)]) -> Self {
+        Self { params }
+    }
+
+    fn single(&self) -> Result<ValueDeserializer<'de>, ParamsError> {
+        match self.params {
+            [(key, value)] => Ok(ValueDeserializer { key, value }),
+            params => Err(ParamsError::Length {
+                expected: 1,
+                found: params.len(),
+            }),
+        }
+    }
+}
+
+macro_rules! forward_to_single {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                self.single()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ParamsDeserializer<'de> {
+    type Error = ParamsError;
+
+    forward_to_single! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_f32
+        deserialize_f64 deserialize_char deserialize_str deserialize_string deserialize_bytes
+        deserialize_byte_buf deserialize_option deserialize_identifier
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_seq(ParamsSeq {
+            params: self.params.iter(),
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        if self.params.len() != len {
+            return Err(ParamsError::Length {
+                expected: len,
+                found: self.params.len(),
+            });
+        }
+
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(ParamsMap {
+            params: self.params.iter(),
+            value: None,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+}
+
+struct ParamsSeq<'de> {
+    params: std::slice::Iter<'de, (String, String)>,
+}
+
+impl<'de> SeqAccess<'de> for ParamsSeq<'de> {
+    type Error = ParamsError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.params.next() {
+            Some((key, value)) => seed.deserialize(ValueDeserializer { key, value }).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.params.len())
+    }
+}
+
+struct ParamsMap<'de> {
+    params: std::slice::Iter<'de, (String, String)>,
+    value: Option<&'de (String, String)>,
+}
+
+impl<'de> MapAccess<'de> for ParamsMap<'de> {
+    type Error = ParamsError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.params.next() {
+            Some(param) => {
+                self.value = Some(param);
+                seed.deserialize(KeyDeserializer(&param.0)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some((key, value)) => seed.deserialize(ValueDeserializer { key, value }),
+            None => Err(ParamsError::Custom("value is missing".into())),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.params.len())
+    }
+}
+
+struct KeyDeserializer<'de>(&'de str);
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer<'de> {
+    type Error = ParamsError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Deserializes the value of a single parameter.
+struct ValueDeserializer<'de> {
+    key: &'de str,
+    value: &'de str,
+}
+
+macro_rules! parse_value {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                match self.value.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(err) => Err(ParamsError::Invalid {
+                        key: self.key.to_owned(),
+                        value: self.value.to_owned(),
+                        message: err.to_string(),
+                    }),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = ParamsError;
+
+    parse_value! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_borrowed_str(self.value)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_enum(self.value.into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf unit unit_struct seq tuple tuple_struct map
+        struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    fn params(params: &[(&str, &str)]) -> Vec<(String, String)> {
+        params
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn deserialize_struct() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct UserPath<'a> {
+            id: u64,
+            name: &'a str,
+            admin: Option<bool>,
+        }
+
+        let params = params(&[("id", "42"), ("name", "gordon")]);
+        let user = UserPath::deserialize(ParamsDeserializer::new(&params)).unwrap();
+        assert_eq!(
+            user,
+            UserPath {
+                id: 42,
+                name: "gordon",
+                admin: None
+            }
+        );
+    }
+
+    #[test]
+    fn deserialize_tuple() {
+        let params = params(&[("org", "rust-lang"), ("issue", "1024")]);
+        let tuple = <(String, u32)>::deserialize(ParamsDeserializer::new(&params)).unwrap();
+        assert_eq!(tuple, ("rust-lang".to_string(), 1024));
+
+        assert_eq!(
+            <(u32,)>::deserialize(ParamsDeserializer::new(&params)),
+            Err(ParamsError::Length {
+                expected: 1,
+                found: 2
+            })
+        );
+    }
+
+    #[test]
+    fn deserialize_single() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(rename_all = "lowercase")]
+        enum Kind {
+            Posts,
+            Comments,
+        }
+
+        for (value, kind) in [("posts", Kind::Posts), ("comments", Kind::Comments)] {
+            let params = params(&[("kind", value)]);
+            assert_eq!(
+                Kind::deserialize(ParamsDeserializer::new(&params)),
+                Ok(kind)
+            );
+        }
+
+        let params = self::params(&[("id", "abc")]);
+        assert!(matches!(
+            u64::deserialize(ParamsDeserializer::new(&params)),
+            Err(ParamsError::Invalid { .. })
+        ));
+    }
+}
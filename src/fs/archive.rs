@@ -0,0 +1,564 @@
+//! File sources that serve the entries of tar and zip archives.
+use super::source::{open_dir, read_dir};
+use super::{DirEntry, FileSource, SourceEntry, SourceFile};
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Files in a tar archive, see [`FileSource`].
+///
+/// The archive is indexed once when the source is created, and the entries are then
+/// read directly from the archive, which must not be modified while it is served.
+/// Only regular files are served, links and other special entries are skipped.
+/// ```rust,no_run
+/// use httprouter::{Router, StaticFiles, TarSource};
+///
+/// # fn run() -> std::io::Result<()> {
+/// let docs = TarSource::new("./docs.tar")?;
+/// let router = Router::default().serve_files("/docs/*filepath", StaticFiles::from_source(docs));
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "tar")]
+#[derive(Clone, Debug)]
+pub struct TarSource {
+    index: Index,
+}
+
+#[cfg(feature = "tar")]
+impl TarSource {
+    /// Indexes the tar archive at the given path.
+    pub fn new(archive: impl Into<PathBuf>) -> io::Result<Self> {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let archive = archive.into();
+        let mut entries = BTreeMap::new();
+
+        let mut tar = tar::Archive::new(File::open(&archive)?);
+        for entry in tar.entries_with_seek()? {
+            let entry = entry?;
+            let header = entry.header();
+            if !header.entry_type().is_file() {
+                continue;
+            }
+
+            let name = match normalize(&entry.path()?.to_string_lossy()) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            let len = entry.size();
+            let mtime = header.mtime().ok();
+            entries.insert(
+                name,
+                Entry {
+                    offset: entry.raw_file_position(),
+                    len,
+                    compression: Compression::Stored,
+                    modified: mtime.map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+                    etag: format!("{:x}-{:x}", mtime.unwrap_or_default(), len),
+                },
+            );
+        }
+
+        Ok(Self {
+            index: Index { archive, entries },
+        })
+    }
+}
+
+#[cfg(feature = "tar")]
+impl FileSource for TarSource {
+    fn open(&self, path: &str) -> io::Result<SourceEntry> {
+        self.index.open(path)
+    }
+
+    fn read_dir(&self, path: &str) -> io::Result<Vec<DirEntry>> {
+        self.index.read_dir(path)
+    }
+}
+
+/// Files in a zip archive, see [`FileSource`].
+///
+/// The archive is indexed once when the source is created, and the entries are then
+/// read directly from the archive, which must not be modified while it is served.
+/// Entries are streamed from the archive. Range requests are supported for stored
+/// entries, while deflated entries are decompressed as they are read, and always
+/// served whole.
+/// Entries that are encrypted or compressed with other methods are skipped.
+/// ```rust,no_run
+/// use httprouter::{Router, StaticFiles, ZipSource};
+///
+/// # fn run() -> std::io::Result<()> {
+/// let docs = ZipSource::new("./docs.zip")?;
+/// let router = Router::default().serve_files("/docs/*filepath", StaticFiles::from_source(docs));
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "zip")]
+#[derive(Clone, Debug)]
+pub struct ZipSource {
+    index: Index,
+}
+
+#[cfg(feature = "zip")]
+impl ZipSource {
+    /// Indexes the zip archive at the given path.
+    pub fn new(archive: impl Into<PathBuf>) -> io::Result<Self> {
+        use zip::result::ZipError;
+        use zip::CompressionMethod;
+
+        let archive = archive.into();
+        let mut entries = BTreeMap::new();
+
+        let mut zip = zip::ZipArchive::new(File::open(&archive)?)?;
+        for i in 0..zip.len() {
+            let file = match zip.by_index(i) {
+                Ok(file) => file,
+                // encrypted entries
+                Err(ZipError::UnsupportedArchive(_)) => continue,
+                Err(err) => return Err(err.into()),
+            };
+
+            let compression = match file.compression() {
+                CompressionMethod::Stored => Compression::Stored,
+                CompressionMethod::Deflated => Compression::Deflated(file.compressed_size()),
+                _ => continue,
+            };
+
+            let name = match normalize(file.name()) {
+                Some(name) if !file.is_dir() => name,
+                _ => continue,
+            };
+
+            entries.insert(
+                name,
+                Entry {
+                    offset: file.data_start(),
+                    len: file.size(),
+                    compression,
+                    modified: system_time(file.last_modified()),
+                    etag: format!("{:08x}-{:x}", file.crc32(), file.size()),
+                },
+            );
+        }
+
+        Ok(Self {
+            index: Index { archive, entries },
+        })
+    }
+}
+
+#[cfg(feature = "zip")]
+impl FileSource for ZipSource {
+    fn open(&self, path: &str) -> io::Result<SourceEntry> {
+        self.index.open(path)
+    }
+
+    fn read_dir(&self, path: &str) -> io::Result<Vec<DirEntry>> {
+        self.index.read_dir(path)
+    }
+}
+
+/// The files of an archive, keyed by their normalized path.
+#[derive(Clone, Debug)]
+struct Index {
+    archive: PathBuf,
+    entries: BTreeMap<String, Entry>,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    // the position of the data in the archive
+    offset: u64,
+    // the uncompressed length
+    len: u64,
+    compression: Compression,
+    modified: Option<SystemTime>,
+    etag: String,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Compression {
+    Stored,
+    // the compressed length
+    #[cfg(feature = "zip")]
+    Deflated(u64),
+}
+
+impl Index {
+    fn open(&self, path: &str) -> io::Result<SourceEntry> {
+        let entry = match self.entries.get(path) {
+            Some(entry) => entry,
+            None => return open_dir(&self.entries, path),
+        };
+
+        let file = match entry.compression {
+            Compression::Stored => {
+                let section = Section::new(self.archive_file()?, entry.offset, entry.len)?;
+                SourceFile::new(section, entry.len)
+            }
+            #[cfg(feature = "zip")]
+            Compression::Deflated(compressed) => {
+                let section = Section::new(self.archive_file()?, entry.offset, compressed)?;
+                SourceFile::new(Inflate::new(section, entry.len), entry.len).without_ranges()
+            }
+        };
+
+        let file = file.etag(entry.etag.clone());
+        Ok(SourceEntry::File(match entry.modified {
+            Some(modified) => file.modified(modified),
+            None => file,
+        }))
+    }
+
+    fn read_dir(&self, path: &str) -> io::Result<Vec<DirEntry>> {
+        read_dir(&self.entries, path, |name, entry| {
+            let file = DirEntry::file(name, entry.len);
+            match entry.modified {
+                Some(modified) => file.modified(modified),
+                None => file,
+            }
+        })
+    }
+
+    fn archive_file(&self) -> io::Result<File> {
+        File::open(&self.archive)
+    }
+}
+
+/// A deflated entry, decompressed as it is read.
+///
+/// The entry can't seek, so range requests are answered with the whole entry.
+#[cfg(feature = "zip")]
+struct Inflate {
+    decoder: flate2::read::DeflateDecoder<Section>,
+    // the uncompressed length
+    len: u64,
+    pos: u64,
+}
+
+#[cfg(feature = "zip")]
+impl Inflate {
+    fn new(section: Section, len: u64) -> Self {
+        Self {
+            decoder: flate2::read::DeflateDecoder::new(section),
+            len,
+            pos: 0,
+        }
+    }
+}
+
+#[cfg(feature = "zip")]
+impl Read for Inflate {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.len - self.pos;
+        let max = (buf.len() as u64).min(remaining) as usize;
+        let n = self.decoder.read(&mut buf[..max])?;
+        if n == 0 && max > 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "archive entry has an unexpected length",
+            ));
+        }
+
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(feature = "zip")]
+impl Seek for Inflate {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match pos {
+            SeekFrom::Start(pos) if pos == self.pos => Ok(pos),
+            SeekFrom::Current(0) => Ok(self.pos),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "deflated archive entries can't seek",
+            )),
+        }
+    }
+}
+
+/// Converts the local date of a zip entry to a system time, assuming UTC.
+#[cfg(feature = "zip")]
+fn system_time(date: zip::DateTime) -> Option<SystemTime> {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    // days since the epoch of the civil date,
+    // see http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let (month, day) = (i64::from(date.month()), i64::from(date.day()));
+    let year = i64::from(date.year()) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let secs = days * 86_400
+        + i64::from(date.hour()) * 3_600
+        + i64::from(date.minute()) * 60
+        + i64::from(date.second());
+
+    if secs < 0 {
+        return None;
+    }
+
+    Some(UNIX_EPOCH + Duration::from_secs(secs as u64))
+}
+
+/// Normalizes the path of an archive entry, returning `None` for paths that escape
+/// the root of the archive.
+fn normalize(path: &str) -> Option<String> {
+    let mut segments = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => return None,
+            segment => segments.push(segment),
+        }
+    }
+
+    if segments.is_empty() {
+        return None;
+    }
+
+    Some(segments.join("/"))
+}
+
+/// A section of a file, read like a file of its own.
+struct Section {
+    file: File,
+    start: u64,
+    len: u64,
+    pos: u64,
+}
+
+impl Section {
+    fn new(mut file: File, start: u64, len: u64) -> io::Result<Self> {
+        file.seek(SeekFrom::Start(start))?;
+        Ok(Self {
+            file,
+            start,
+            len,
+            pos: 0,
+        })
+    }
+}
+
+impl Read for Section {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.len.saturating_sub(self.pos);
+        let max = (buf.len() as u64).min(remaining) as usize;
+        let n = self.file.read(&mut buf[..max])?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for Section {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::End(offset) => offset_by(self.len, offset),
+            SeekFrom::Current(offset) => offset_by(self.pos, offset),
+        };
+
+        let pos = pos.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "seek to a negative position")
+        })?;
+
+        self.file.seek(SeekFrom::Start(self.start + pos))?;
+        self.pos = pos;
+        Ok(pos)
+    }
+}
+
+fn offset_by(pos: u64, offset: i64) -> Option<u64> {
+    if offset < 0 {
+        pos.checked_sub(offset.unsigned_abs())
+    } else {
+        pos.checked_add(offset as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize("./docs/index.html"),
+            Some("docs/index.html".to_owned())
+        );
+        assert_eq!(normalize("docs//guide/"), Some("docs/guide".to_owned()));
+        assert_eq!(normalize("../secret"), None);
+        assert_eq!(normalize("./"), None);
+    }
+
+    #[cfg(any(feature = "tar", feature = "zip"))]
+    async fn serve_archive(files: super::super::StaticFiles, deflated: bool) {
+        use crate::Router;
+        use hyper::{header, Body, Request, StatusCode};
+
+        let router = Router::default().serve_files("/docs/*filepath", files.listing());
+        let get = |path: &str, range: Option<&str>| {
+            let mut req = Request::get(path);
+            if let Some(range) = range {
+                req = req.header(header::RANGE, range);
+            }
+            router.serve(req.body(Body::empty()).unwrap())
+        };
+
+        let res = get("/docs/", None).await.unwrap();
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "text/html");
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "<h1>Docs</h1>");
+
+        for path in ["/docs/css/stored.css", "/docs/css/deflated.css"].iter() {
+            let res = get(path, None).await.unwrap();
+            assert_eq!(res.status(), StatusCode::OK, "{}", path);
+            assert_eq!(res.headers()[header::CONTENT_TYPE], "text/css");
+            assert!(res.headers().contains_key(header::ETAG));
+            let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            assert_eq!(body, "0123456789".repeat(100), "{}", path);
+
+            // ranges of deflated entries are answered with the whole entry
+            let res = get(path, Some("bytes=995-")).await.unwrap();
+            if deflated && path.contains("deflated") {
+                assert_eq!(res.status(), StatusCode::OK, "{}", path);
+                assert_eq!(res.headers()[header::ACCEPT_RANGES], "none");
+                let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+                assert_eq!(body, "0123456789".repeat(100), "{}", path);
+            } else {
+                assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT, "{}", path);
+                assert_eq!(res.headers()[header::CONTENT_RANGE], "bytes 995-999/1000");
+                let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+                assert_eq!(body, "56789", "{}", path);
+            }
+        }
+
+        let res = get("/docs/css/", None).await.unwrap();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert!(std::str::from_utf8(&body).unwrap().contains("deflated.css"));
+
+        let res = get("/docs/missing.css", None).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[cfg(feature = "tar")]
+    #[tokio::test]
+    async fn serve_tar() {
+        let path = std::env::temp_dir().join(format!("httprouter-{}.tar", std::process::id()));
+        let mut builder = tar::Builder::new(File::create(&path).unwrap());
+        for (name, contents) in [
+            ("./index.html", "<h1>Docs</h1>".to_owned()),
+            ("css/stored.css", "0123456789".repeat(100)),
+            ("css/deflated.css", "0123456789".repeat(100)),
+        ]
+        .iter()
+        {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(1_600_000_000);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, contents.as_bytes())
+                .unwrap();
+        }
+        builder.finish().unwrap();
+        drop(builder);
+
+        let source = TarSource::new(&path).unwrap();
+        serve_archive(super::super::StaticFiles::from_source(source), false).await;
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "zip")]
+    #[tokio::test]
+    async fn serve_zip() {
+        use std::io::Write;
+        use zip::write::FileOptions;
+        use zip::CompressionMethod;
+
+        let path = std::env::temp_dir().join(format!("httprouter-{}.zip", std::process::id()));
+        let mut zip = zip::ZipWriter::new(File::create(&path).unwrap());
+        let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+        let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        zip.add_directory("css/", stored).unwrap();
+        for (name, contents, options) in [
+            ("index.html", "<h1>Docs</h1>".to_owned(), stored),
+            ("css/stored.css", "0123456789".repeat(100), stored),
+            ("css/deflated.css", "0123456789".repeat(100), deflated),
+        ]
+        .iter()
+        {
+            zip.start_file(*name, *options).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        drop(zip);
+
+        let source = ZipSource::new(&path).unwrap();
+        serve_archive(super::super::StaticFiles::from_source(source), true).await;
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn test_inflate() {
+        use flate2::{write::DeflateEncoder, Compression};
+        use std::io::Write;
+
+        let path = std::env::temp_dir().join(format!("httprouter-{}.deflate", std::process::id()));
+        let mut encoder = DeflateEncoder::new(File::create(&path).unwrap(), Compression::default());
+        encoder.write_all(b"0123456789").unwrap();
+        let compressed = encoder.finish().unwrap().metadata().unwrap().len();
+
+        let open = |len| {
+            Inflate::new(
+                Section::new(File::open(&path).unwrap(), 0, compressed).unwrap(),
+                len,
+            )
+        };
+
+        let mut inflate = open(10);
+        inflate.seek(SeekFrom::Start(0)).unwrap();
+        let mut data = Vec::new();
+        inflate.read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"0123456789");
+
+        // entries can only seek to their current position
+        let mut inflate = open(10);
+        let err = inflate.seek(SeekFrom::Start(7)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+
+        // entries that are shorter than the length recorded in the archive are errors,
+        // longer ones are truncated to it
+        let err = open(11).read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let mut data = Vec::new();
+        open(9).read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"012345678");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn test_system_time() {
+        let date = zip::DateTime::from_date_and_time(2021, 3, 14, 15, 9, 26).unwrap();
+        let time = system_time(date).unwrap();
+        assert_eq!(
+            httpdate::HttpDate::from(time).to_string(),
+            "Sun, 14 Mar 2021 15:09:26 GMT"
+        );
+    }
+}
@@ -0,0 +1,157 @@
+//! Content hashes of static files, for serving them under fingerprinted URLs.
+use super::source::Fnv1a;
+use super::{join, FileSource, SourceEntry, CHUNK_SIZE};
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hasher;
+use std::io::{self, Read};
+use std::sync::Arc;
+
+/// The fingerprinted paths of static files, see [`StaticFiles::fingerprint`].
+///
+/// A fingerprinted path contains a hash of the contents of the file before its
+/// extension, such as `css/app.3f9a2c1b.css` for `css/app.css`.
+///
+/// [`StaticFiles::fingerprint`]: crate::StaticFiles::fingerprint
+#[derive(Clone, Debug, Default)]
+pub struct AssetManifest {
+    // the fingerprinted path of each file
+    assets: Arc<BTreeMap<String, String>>,
+    // the file of each fingerprinted path
+    files: Arc<HashMap<String, String>>,
+}
+
+impl AssetManifest {
+    /// Hashes the contents of every file in the source.
+    pub(crate) fn build(source: &dyn FileSource) -> io::Result<Self> {
+        let mut assets = BTreeMap::new();
+        let mut dirs = vec![String::new()];
+
+        while let Some(dir) = dirs.pop() {
+            let entries = match source.read_dir(&dir) {
+                Ok(entries) => entries,
+                // an empty source
+                Err(err) if err.kind() == io::ErrorKind::NotFound && dir.is_empty() => break,
+                Err(err) => return Err(err),
+            };
+
+            for entry in entries {
+                let path = join(&dir, &entry.name);
+                if entry.is_dir {
+                    dirs.push(path);
+                    continue;
+                }
+
+                let mut file = match source.open(&path)? {
+                    SourceEntry::File(file) => file,
+                    SourceEntry::Directory => continue,
+                };
+
+                let mut hasher = Fnv1a::default();
+                let mut buf = vec![0; CHUNK_SIZE as usize];
+                loop {
+                    match file.reader.read(&mut buf)? {
+                        0 => break,
+                        n => hasher.write(&buf[..n]),
+                    }
+                }
+
+                let fingerprinted = fingerprint(&path, hasher.finish());
+                assets.insert(path, fingerprinted);
+            }
+        }
+
+        let files = assets
+            .iter()
+            .map(|(path, fingerprinted)| (fingerprinted.clone(), path.clone()))
+            .collect();
+
+        Ok(Self {
+            assets: Arc::new(assets),
+            files: Arc::new(files),
+        })
+    }
+
+    /// Returns the fingerprinted path of the file at the given path, relative to the
+    /// root of the files, or `None` if there is no such file.
+    /// ```rust
+    /// use httprouter::{MemorySource, StaticFiles};
+    ///
+    /// let source = MemorySource::new().insert("js/app.js", "render()");
+    /// let files = StaticFiles::from_source(source).fingerprint().unwrap();
+    /// let manifest = files.manifest().unwrap();
+    ///
+    /// let url = format!("/static/{}", manifest.asset_url("js/app.js").unwrap());
+    /// assert!(url.starts_with("/static/js/app.") && url.ends_with(".js"));
+    /// ```
+    pub fn asset_url(&self, path: &str) -> Option<&str> {
+        self.assets
+            .get(path.trim_start_matches('/'))
+            .map(String::as_str)
+    }
+
+    /// Returns an iterator over the path and fingerprinted path of every file, sorted
+    /// by path, for example to write the manifest to a file.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.assets
+            .iter()
+            .map(|(path, fingerprinted)| (path.as_str(), fingerprinted.as_str()))
+    }
+
+    /// Returns the path of the file that the fingerprinted path refers to.
+    pub(crate) fn file(&self, fingerprinted: &str) -> Option<&str> {
+        self.files.get(fingerprinted).map(String::as_str)
+    }
+}
+
+/// Inserts the hash into the name of the file at the path, before its extension.
+fn fingerprint(path: &str, hash: u64) -> String {
+    let hash = format!("{:08x}", hash >> 32);
+    let name_start = path.rfind('/').map_or(0, |i| i + 1);
+
+    match path[name_start..].rfind('.') {
+        // names such as `.env` have no extension
+        Some(i) if i > 0 => {
+            let (stem, extension) = path.split_at(name_start + i);
+            [stem, ".", &hash, extension].concat()
+        }
+        _ => [path, ".", &hash].concat(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemorySource;
+
+    #[test]
+    fn test_fingerprint() {
+        let hash = 0x3f9a_2c1b_0000_0000;
+        assert_eq!(fingerprint("app.js", hash), "app.3f9a2c1b.js");
+        assert_eq!(
+            fingerprint("css/app.min.css", hash),
+            "css/app.min.3f9a2c1b.css"
+        );
+        assert_eq!(fingerprint("v1.2/LICENSE", hash), "v1.2/LICENSE.3f9a2c1b");
+        assert_eq!(fingerprint(".env", hash), ".env.3f9a2c1b");
+    }
+
+    #[test]
+    fn build_manifest() {
+        let source = MemorySource::new()
+            .insert("app.js", "a")
+            .insert("css/app.css", "b")
+            .insert("css/vendor/reset.css", "b");
+        let manifest = AssetManifest::build(&source).unwrap();
+
+        let hash = format!("{:08x}", super::super::source::fnv1a(b"b") >> 32);
+        let css = format!("css/app.{}.css", hash);
+        assert_eq!(manifest.asset_url("/css/app.css"), Some(css.as_str()));
+        assert_eq!(manifest.file(&css), Some("css/app.css"));
+        assert_eq!(manifest.iter().count(), 3);
+        assert_eq!(manifest.asset_url("missing.js"), None);
+
+        let empty = AssetManifest::build(&MemorySource::new()).unwrap();
+        assert_eq!(empty.iter().count(), 0);
+    }
+}
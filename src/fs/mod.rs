@@ -0,0 +1,1385 @@
+//! Serving static files with [`Router::serve_files`](crate::Router::serve_files).
+use crate::path::{clean, encode_segment};
+use crate::redirect::{Redirect, RouterRedirects};
+use crate::router::Params;
+
+#[cfg(any(feature = "tar", feature = "zip"))]
+mod archive;
+mod manifest;
+mod source;
+
+#[cfg(feature = "tar")]
+pub use archive::TarSource;
+#[cfg(feature = "zip")]
+pub use archive::ZipSource;
+pub use manifest::AssetManifest;
+pub use source::{DirEntry, DiskSource, FileSource, MemorySource, SourceEntry, SourceFile};
+
+use std::collections::hash_map::RandomState;
+use std::convert::Infallible;
+use std::fmt;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, SeekFrom};
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::SystemTime;
+
+use httpdate::HttpDate;
+use hyper::body::{Bytes, Sender};
+use hyper::header::{self, HeaderMap, HeaderValue};
+use hyper::service::Service;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use percent_encoding::percent_decode_str;
+use tokio::runtime::Handle;
+
+/// The name of the catch-all parameter that captures the requested file.
+pub(crate) const FILEPATH: &str = "filepath";
+
+/// The size of the chunks that files are streamed in.
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// The content codings of precompressed files, and the extension of the files, in
+/// order of preference.
+const ENCODINGS: [(&str, &str); 2] = [("br", "br"), ("gzip", "gz")];
+
+/// The caching policy of files requested by their fingerprinted path, whose contents
+/// never change.
+const CACHE_IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// The caching policy of fingerprinted files requested by their plain path, whose
+/// contents may change with the next deployment.
+const CACHE_SHORT: &str = "public, max-age=60";
+
+/// Static files served with [`Router::serve_files`](crate::Router::serve_files), from a
+/// directory on disk or any other [`FileSource`].
+/// ```rust
+/// use httprouter::{Router, StaticFiles};
+///
+/// let files = StaticFiles::new("./public").precompressed();
+/// let router = Router::default().serve_files("/static/*filepath", files);
+/// ```
+#[derive(Clone)]
+pub struct StaticFiles {
+    source: Arc<dyn FileSource>,
+    precompressed: bool,
+    index_file: Option<String>,
+    listing: bool,
+    manifest: Option<AssetManifest>,
+}
+
+impl StaticFiles {
+    /// Serve the files in the given root directory.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self::from_source(DiskSource::new(root))
+    }
+
+    /// Serve the files of the given source.
+    pub fn from_source(source: impl FileSource) -> Self {
+        Self {
+            source: Arc::new(source),
+            precompressed: false,
+            index_file: Some("index.html".to_owned()),
+            listing: false,
+            manifest: None,
+        }
+    }
+
+    /// If enabled, precompressed siblings of the requested file, such as `app.js.br` or
+    /// `app.js.gz`, are served instead of the file itself when the client accepts their
+    /// encoding. Brotli is preferred over gzip when both are equally acceptable, and the
+    /// uncompressed file is served if neither is available.
+    pub fn precompressed(mut self) -> Self {
+        self.precompressed = true;
+        self
+    }
+
+    /// The file that is served when a directory is requested, `index.html` by default.
+    pub fn index_file(mut self, name: impl Into<String>) -> Self {
+        self.index_file = Some(name.into());
+        self
+    }
+
+    /// If enabled, directories without an index file are rendered as a listing of
+    /// their entries, in JSON if the client accepts `application/json` and not
+    /// `text/html`, and in HTML otherwise. Listings are disabled by default, and
+    /// such directories are reported as missing.
+    pub fn listing(mut self) -> Self {
+        self.listing = true;
+        self
+    }
+
+    /// Hashes the contents of every file up front, and serves each file under a
+    /// fingerprinted path as well, such as `app.3f9a2c1b.js` for `app.js`. Responses for
+    /// fingerprinted paths are cached indefinitely with `Cache-Control: immutable`, while
+    /// the plain paths keep working with a short caching period. The fingerprinted paths
+    /// can be looked up in the [`AssetManifest`] returned by [`StaticFiles::manifest`].
+    ///
+    /// Files are found by listing the directories of the source, so sources must
+    /// implement [`FileSource::read_dir`]. Files added to the source later are served
+    /// under their plain path only.
+    /// ```rust,no_run
+    /// use httprouter::{Router, StaticFiles};
+    ///
+    /// # fn run() -> std::io::Result<()> {
+    /// let files = StaticFiles::new("./public").fingerprint()?;
+    /// let manifest = files.manifest().unwrap().clone();
+    /// let router = Router::default().serve_files("/static/*filepath", files);
+    ///
+    /// // `/static/app.3f9a2c1b.js`
+    /// let url = format!("/static/{}", manifest.asset_url("app.js").unwrap());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn fingerprint(mut self) -> io::Result<Self> {
+        self.manifest = Some(AssetManifest::build(&*self.source)?);
+        Ok(self)
+    }
+
+    /// Returns the fingerprinted paths of the files, if [`StaticFiles::fingerprint`]
+    /// was enabled.
+    pub fn manifest(&self) -> Option<&AssetManifest> {
+        self.manifest.as_ref()
+    }
+}
+
+impl fmt::Debug for StaticFiles {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StaticFiles")
+            .field("precompressed", &self.precompressed)
+            .field("index_file", &self.index_file)
+            .field("listing", &self.listing)
+            .field("manifest", &self.manifest)
+            .finish()
+    }
+}
+
+impl From<PathBuf> for StaticFiles {
+    fn from(root: PathBuf) -> Self {
+        StaticFiles::new(root)
+    }
+}
+
+impl From<&PathBuf> for StaticFiles {
+    fn from(root: &PathBuf) -> Self {
+        StaticFiles::new(root)
+    }
+}
+
+impl From<&Path> for StaticFiles {
+    fn from(root: &Path) -> Self {
+        StaticFiles::new(root)
+    }
+}
+
+impl From<String> for StaticFiles {
+    fn from(root: String) -> Self {
+        StaticFiles::new(root)
+    }
+}
+
+impl From<&str> for StaticFiles {
+    fn from(root: &str) -> Self {
+        StaticFiles::new(root)
+    }
+}
+
+/// A service that serves static files, see
+/// [`Router::serve_files`](crate::Router::serve_files).
+#[derive(Clone)]
+pub(crate) struct ServeFiles {
+    files: Arc<StaticFiles>,
+    // serve the index file of the root for every request
+    fallback: bool,
+}
+
+impl ServeFiles {
+    pub(crate) fn new(files: StaticFiles) -> Self {
+        Self {
+            files: Arc::new(files),
+            fallback: false,
+        }
+    }
+
+    /// A service that answers every request with the index file of the root, see
+    /// [`Router::spa_fallback`](crate::Router::spa_fallback).
+    pub(crate) fn fallback(files: StaticFiles) -> Self {
+        Self {
+            files: Arc::new(files),
+            fallback: true,
+        }
+    }
+}
+
+impl Service<Request<Body>> for ServeFiles {
+    type Response = Response<Body>;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Response<Body>, Infallible>> + Send + Sync>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let files = self.files.clone();
+        let fallback = self.fallback;
+        Box::pin(async move { Ok(serve(files, req, fallback).await) })
+    }
+}
+
+async fn serve(files: Arc<StaticFiles>, req: Request<Body>, fallback: bool) -> Response<Body> {
+    let path = match requested_path(&req) {
+        Some(path) => path,
+        None if fallback => String::new(),
+        None => return status(StatusCode::BAD_REQUEST),
+    };
+
+    let (path, cache_control) = match files.manifest {
+        Some(ref manifest) => match manifest.file(&path) {
+            Some(file) => (file.to_owned(), Some(CACHE_IMMUTABLE)),
+            None => (path, Some(CACHE_SHORT)),
+        },
+        None => (path, None),
+    };
+
+    // directories are redirected to the path with a trailing slash, like routes
+    let redirects = req
+        .extensions()
+        .get::<RouterRedirects>()
+        .cloned()
+        .unwrap_or_default();
+    let redirect = redirects.trailing_slash;
+
+    let trailing_slash = req.uri().path().ends_with('/');
+    let precompressed = files.precompressed;
+    let encodings = if precompressed {
+        accepted_encodings(&req)
+    } else {
+        Vec::new()
+    };
+
+    let resolve = move |files: &StaticFiles| {
+        if fallback {
+            index(files, "", &encodings)
+        } else {
+            resolve(files, &path, trailing_slash, redirect, &encodings)
+        }
+    };
+
+    let blocking = files.source.blocking();
+    let resolved = if blocking {
+        let files = files.clone();
+        match tokio::task::spawn_blocking(move || resolve(&files)).await {
+            Ok(resolved) => resolved,
+            Err(_) => return status(StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    } else {
+        resolve(&files)
+    };
+
+    match resolved {
+        Ok(Target::File {
+            file,
+            path,
+            encoding,
+        }) => {
+            let content_type = mime_guess::from_path(&path)
+                .first_or_octet_stream()
+                .to_string();
+
+            let mut res = respond(&req, file, &content_type, blocking);
+            if let Some(encoding) = encoding {
+                res.headers_mut()
+                    .insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding));
+            }
+
+            if precompressed {
+                res.headers_mut().insert(
+                    header::VARY,
+                    HeaderValue::from_static(header::ACCEPT_ENCODING.as_str()),
+                );
+            }
+
+            if let Some(cache_control) = cache_control {
+                res.headers_mut().insert(
+                    header::CACHE_CONTROL,
+                    HeaderValue::from_static(cache_control),
+                );
+            }
+
+            res
+        }
+        Ok(Target::Redirect) => {
+            let path = req.uri().path();
+            let path = if trailing_slash {
+                path.trim_end_matches('/').to_owned()
+            } else {
+                [path, "/"].concat()
+            };
+
+            let policy = &redirects.policy;
+            match policy.status(Redirect::TrailingSlash, req.method()) {
+                Some(status) => Response::builder()
+                    .status(status)
+                    .header(header::LOCATION, policy.location(&req, &path))
+                    .body(Body::empty())
+                    .unwrap(),
+                None => status(StatusCode::NOT_FOUND),
+            }
+        }
+        Ok(Target::Listing(entries)) => listing(&req, entries),
+        Err(err) if err.kind() == io::ErrorKind::PermissionDenied => status(StatusCode::FORBIDDEN),
+        // other errors, such as a path component that is not a directory, are
+        // reported as missing files
+        Err(_) => status(StatusCode::NOT_FOUND),
+    }
+}
+
+/// What to respond to a request with.
+enum Target {
+    File {
+        file: SourceFile,
+        // the path that the content type is guessed from
+        path: String,
+        encoding: Option<&'static str>,
+    },
+    // add or remove the trailing slash of the request path
+    Redirect,
+    Listing(Vec<DirEntry>),
+}
+
+/// Resolves the requested path in the source.
+fn resolve(
+    files: &StaticFiles,
+    path: &str,
+    trailing_slash: bool,
+    redirect: bool,
+    encodings: &[(&'static str, &'static str)],
+) -> io::Result<Target> {
+    match files.source.open(path)? {
+        SourceEntry::File(_) if trailing_slash && redirect => Ok(Target::Redirect),
+        SourceEntry::File(file) => open_encoded(files, path, file, encodings),
+        SourceEntry::Directory if !trailing_slash && redirect => Ok(Target::Redirect),
+        SourceEntry::Directory => match index(files, path, encodings) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound && files.listing => {
+                let mut entries = files.source.read_dir(path)?;
+                entries.sort_by(|a, b| a.name.cmp(&b.name));
+                Ok(Target::Listing(entries))
+            }
+            target => target,
+        },
+    }
+}
+
+/// Opens the index file of the directory at the path.
+fn index(
+    files: &StaticFiles,
+    path: &str,
+    encodings: &[(&'static str, &'static str)],
+) -> io::Result<Target> {
+    let index = match files.index_file {
+        Some(ref index) => join(path, index),
+        None => return Err(io::ErrorKind::NotFound.into()),
+    };
+
+    match files.source.open(&index)? {
+        SourceEntry::File(file) => open_encoded(files, &index, file, encodings),
+        SourceEntry::Directory => Err(io::ErrorKind::NotFound.into()),
+    }
+}
+
+/// Returns the most preferred precompressed sibling of the file at the path, or the
+/// file itself if there is none.
+fn open_encoded(
+    files: &StaticFiles,
+    path: &str,
+    file: SourceFile,
+    encodings: &[(&'static str, &'static str)],
+) -> io::Result<Target> {
+    for &(encoding, extension) in encodings {
+        if let Ok(SourceEntry::File(file)) = files.source.open(&[path, ".", extension].concat()) {
+            return Ok(Target::File {
+                file,
+                path: path.to_owned(),
+                encoding: Some(encoding),
+            });
+        }
+    }
+
+    Ok(Target::File {
+        file,
+        path: path.to_owned(),
+        encoding: None,
+    })
+}
+
+/// Joins a name onto a path of a source.
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_owned()
+    } else {
+        [path, "/", name].concat()
+    }
+}
+
+/// Renders a directory listing as JSON if the client prefers it, or HTML otherwise.
+fn listing(req: &Request<Body>, entries: Vec<DirEntry>) -> Response<Body> {
+    let accept = req
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok());
+    let json = matches!(
+        accept,
+        Some(accept) if accept.contains("application/json") && !accept.contains("text/html")
+    );
+
+    let (content_type, body) = if json {
+        ("application/json", listing_json(&entries))
+    } else {
+        (
+            "text/html; charset=utf-8",
+            listing_html(req.uri().path(), &entries),
+        )
+    };
+
+    let len = body.len();
+    let body = if req.method() == Method::HEAD {
+        Body::empty()
+    } else {
+        Body::from(body)
+    };
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CONTENT_LENGTH, len)
+        .body(body)
+        .unwrap()
+}
+
+fn listing_html(path: &str, entries: &[DirEntry]) -> String {
+    let title = escape_html(&percent_decode_str(path).decode_utf8_lossy());
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Index of {0}</title>\n</head>\n<body>\n<h1>Index of {0}</h1>\n<ul>\n",
+        title
+    );
+
+    if path != "/" {
+        html.push_str("<li><a href=\"../\">../</a></li>\n");
+    }
+
+    for entry in entries {
+        let slash = if entry.is_dir { "/" } else { "" };
+        html.push_str(&format!(
+            "<li><a href=\"{}{}\">{}{}</a></li>\n",
+            encode_segment(&entry.name),
+            slash,
+            escape_html(&entry.name),
+            slash
+        ));
+    }
+
+    html.push_str("</ul>\n</body>\n</html>\n");
+    html
+}
+
+fn listing_json(entries: &[DirEntry]) -> String {
+    let entries = entries
+        .iter()
+        .map(|entry| {
+            let modified = match entry.modified {
+                Some(modified) => format!("\"{}\"", HttpDate::from(modified)),
+                None => "null".to_owned(),
+            };
+
+            format!(
+                "{{\"name\":\"{}\",\"dir\":{},\"size\":{},\"modified\":{}}}",
+                escape_json(&entry.name),
+                entry.is_dir,
+                entry.len,
+                modified
+            )
+        })
+        .collect::<Vec<_>>();
+
+    format!("[{}]", entries.join(","))
+}
+
+pub(crate) fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub(crate) fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Returns the precompressed encodings accepted by the client, and the extension of
+/// their files, in order of preference.
+fn accepted_encodings(req: &Request<Body>) -> Vec<(&'static str, &'static str)> {
+    let mut accepted = Vec::new();
+    let mut any = None;
+
+    for value in req.headers().get_all(header::ACCEPT_ENCODING) {
+        let value = match value.to_str() {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+
+        for coding in value.split(',') {
+            let mut parts = coding.split(';').map(str::trim);
+            let name = parts.next().unwrap_or_default();
+            let q = parts
+                .find_map(|param| param.strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.parse::<f32>().ok())
+                .unwrap_or(0.0);
+
+            if name == "*" {
+                any = Some(q);
+            } else if let Some(encoding) = ENCODINGS.iter().find(|(encoding, _)| {
+                name.eq_ignore_ascii_case(encoding)
+                    || (*encoding == "gzip" && name.eq_ignore_ascii_case("x-gzip"))
+            }) {
+                accepted.push((encoding.0, q));
+            }
+        }
+    }
+
+    let mut encodings = ENCODINGS
+        .iter()
+        .filter_map(|&(encoding, extension)| {
+            let q = accepted
+                .iter()
+                .find(|(accepted, _)| *accepted == encoding)
+                .map(|(_, q)| *q)
+                .or(any)?;
+
+            if q > 0.0 {
+                Some(((encoding, extension), q))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // the sort is stable, so ties keep the preferred order
+    encodings.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+    encodings
+        .into_iter()
+        .map(|(encoding, _)| encoding)
+        .collect()
+}
+
+/// Returns `true` if the request explicitly accepts `text/html`, as browsers do when
+/// navigating to a page, but not when loading scripts or images.
+pub(crate) fn accepts_html(headers: &HeaderMap) -> bool {
+    let accept = match headers.get(header::ACCEPT) {
+        Some(accept) => accept.to_str().unwrap_or_default(),
+        None => return false,
+    };
+
+    accept.split(',').any(|range| {
+        let mut parts = range.split(';').map(str::trim);
+        let html = matches!(parts.next(), Some(ty) if ty.eq_ignore_ascii_case("text/html"));
+        let rejected = parts.any(|param| {
+            matches!(
+                param.split_once('='),
+                Some((q, value)) if q.eq_ignore_ascii_case("q")
+                    && matches!(value.trim().parse::<f32>(), Ok(q) if q == 0.0)
+            )
+        });
+        html && !rejected
+    })
+}
+
+/// Returns the path of the requested file relative to the root of the source, or
+/// `None` if the captured path is malformed.
+fn requested_path(req: &Request<Body>) -> Option<String> {
+    let params = req.extensions().get::<Params>()?;
+    // parameters are percent-decoded by the router
+    let path = params.get(FILEPATH)?;
+
+    if path.contains(&['\0', '\\'][..]) {
+        return None;
+    }
+
+    let path = clean(path);
+    let path = path.trim_matches('/');
+
+    // `clean` removes any `..`, but prefixes such as `C:` must not be joined onto a root
+    if Path::new(path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        Some(path.to_owned())
+    } else {
+        None
+    }
+}
+
+fn respond(
+    req: &Request<Body>,
+    file: SourceFile,
+    content_type: &str,
+    blocking: bool,
+) -> Response<Body> {
+    let len = file.len;
+    let modified = file.modified.map(truncate);
+    let etag = file.etag.as_ref().map(|etag| format!("\"{}\"", etag));
+
+    let accept_ranges = if file.ranges { "bytes" } else { "none" };
+    let mut res = Response::builder().header(header::ACCEPT_RANGES, accept_ranges);
+    if let Some(modified) = modified {
+        res = res.header(header::LAST_MODIFIED, HttpDate::from(modified).to_string());
+    }
+
+    if let Some(ref etag) = etag {
+        res = res.header(header::ETAG, etag);
+    }
+
+    if !modified_since(req, modified, etag.as_deref()) {
+        return res
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Body::empty())
+            .unwrap();
+    }
+
+    let ranges = match req.headers().get(header::RANGE) {
+        Some(range) if file.ranges && if_range(req, modified, etag.as_deref()) => range
+            .to_str()
+            .ok()
+            .and_then(|range| parse_range(range, len)),
+        _ => None,
+    };
+
+    let (res, chunks, content_length) = match ranges {
+        None => (
+            res.status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, content_type),
+            vec![Chunk::File(0..len)],
+            len,
+        ),
+        Some(ranges) if ranges.is_empty() => {
+            return res
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+                .body(Body::empty())
+                .unwrap();
+        }
+        Some(mut ranges) if ranges.len() == 1 => {
+            let range = ranges.remove(0);
+            (
+                res.status(StatusCode::PARTIAL_CONTENT)
+                    .header(header::CONTENT_TYPE, content_type)
+                    .header(header::CONTENT_RANGE, content_range(&range, len)),
+                vec![Chunk::File(range.clone())],
+                range.end - range.start,
+            )
+        }
+        Some(ranges) => {
+            let boundary = boundary();
+            let (chunks, content_length) = multipart(&ranges, len, content_type, &boundary);
+            (
+                res.status(StatusCode::PARTIAL_CONTENT).header(
+                    header::CONTENT_TYPE,
+                    format!("multipart/byteranges; boundary={}", boundary),
+                ),
+                chunks,
+                content_length,
+            )
+        }
+    };
+
+    let body = if req.method() == Method::HEAD {
+        Body::empty()
+    } else if blocking {
+        stream(file, chunks)
+    } else {
+        let mut body = Vec::with_capacity(content_length as usize);
+        let result = write(file, chunks, |bytes| {
+            body.extend_from_slice(&bytes);
+            Ok(())
+        });
+
+        match result {
+            Ok(()) => Body::from(body),
+            Err(_) => return status(StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    };
+
+    res.header(header::CONTENT_LENGTH, content_length)
+        .body(body)
+        .unwrap()
+}
+
+/// Truncates a time to whole seconds, the precision of HTTP dates.
+fn truncate(time: SystemTime) -> SystemTime {
+    let date = HttpDate::from(time);
+    SystemTime::from(date)
+}
+
+/// Returns `false` if the request is conditional, and the file was not modified
+/// according to its `If-None-Match` or `If-Modified-Since` header.
+fn modified_since(req: &Request<Body>, modified: Option<SystemTime>, etag: Option<&str>) -> bool {
+    // `If-None-Match` takes precedence over `If-Modified-Since`
+    if let Some(tags) = req.headers().get(header::IF_NONE_MATCH) {
+        let tags = tags.to_str().unwrap_or_default();
+        return match etag {
+            Some(etag) => {
+                !tags.trim().eq("*") && !tags.split(',').any(|tag| weak_eq(tag.trim(), etag))
+            }
+            None => true,
+        };
+    }
+
+    let since = req
+        .headers()
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|since| since.to_str().ok())
+        .and_then(|since| since.parse::<HttpDate>().ok());
+
+    !matches!((since, modified), (Some(since), Some(modified)) if modified <= SystemTime::from(since))
+}
+
+/// Compares two entity tags, ignoring whether they are weak.
+fn weak_eq(a: &str, b: &str) -> bool {
+    a.trim_start_matches("W/") == b.trim_start_matches("W/")
+}
+
+/// Returns `true` if the `Range` header should be honored, according to the `If-Range`
+/// header of the request.
+fn if_range(req: &Request<Body>, modified: Option<SystemTime>, etag: Option<&str>) -> bool {
+    let value = match req.headers().get(header::IF_RANGE) {
+        Some(value) => value.to_str().unwrap_or_default(),
+        None => return true,
+    };
+
+    // entity tags must match exactly, and weak tags never match
+    if value.starts_with('"') {
+        return matches!(etag, Some(etag) if etag == value);
+    }
+
+    let date = value.parse::<HttpDate>().ok();
+    matches!((date, modified), (Some(date), Some(modified)) if SystemTime::from(date) == modified)
+}
+
+/// Parses the value of a `Range` header into the satisfiable ranges of a file with the
+/// given length.
+///
+/// Returns `None` if the header is malformed, or requests more data than the file
+/// contains, in which case it should be ignored. An empty list means that none of the
+/// ranges are satisfiable.
+fn parse_range(value: &str, len: u64) -> Option<Vec<Range<u64>>> {
+    let (unit, specs) = value.split_once('=')?;
+    if !unit.trim().eq_ignore_ascii_case("bytes") {
+        return None;
+    }
+
+    let mut ranges = Vec::new();
+    for spec in specs
+        .split(',')
+        .map(str::trim)
+        .filter(|spec| !spec.is_empty())
+    {
+        let (start, end) = spec.split_once('-')?;
+        let (start, end) = (start.trim(), end.trim());
+
+        let range = if start.is_empty() {
+            // the last `n` bytes of the file
+            let n = end.parse::<u64>().ok()?;
+            len.saturating_sub(n)..len
+        } else {
+            let start = start.parse::<u64>().ok()?;
+            let end = match end {
+                "" => len,
+                end => {
+                    let end = end.parse::<u64>().ok()?;
+                    if end < start {
+                        return None;
+                    }
+                    end.saturating_add(1).min(len)
+                }
+            };
+            start..end
+        };
+
+        if range.start < range.end {
+            ranges.push(range);
+        }
+    }
+
+    // a client requesting the same bytes many times over is likely malicious
+    let total = ranges
+        .iter()
+        .map(|range| range.end - range.start)
+        .sum::<u64>();
+    if total > len {
+        return None;
+    }
+
+    Some(ranges)
+}
+
+fn content_range(range: &Range<u64>, len: u64) -> String {
+    format!("bytes {}-{}/{}", range.start, range.end - 1, len)
+}
+
+/// Returns a random multipart boundary.
+fn boundary() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|time| time.as_nanos())
+            .unwrap_or_default(),
+    );
+    format!("{:016x}", hasher.finish())
+}
+
+/// Builds the parts of a `multipart/byteranges` body, returning them along with the
+/// total length of the body.
+fn multipart(
+    ranges: &[Range<u64>],
+    len: u64,
+    content_type: &str,
+    boundary: &str,
+) -> (Vec<Chunk>, u64) {
+    let mut chunks = Vec::with_capacity(ranges.len() * 2 + 1);
+    let mut total = 0;
+
+    for (i, range) in ranges.iter().enumerate() {
+        let header = format!(
+            "{}--{}\r\n{}: {}\r\n{}: {}\r\n\r\n",
+            if i == 0 { "" } else { "\r\n" },
+            boundary,
+            header::CONTENT_TYPE,
+            content_type,
+            header::CONTENT_RANGE,
+            content_range(range, len)
+        );
+
+        total += header.len() as u64 + range.end - range.start;
+        chunks.push(Chunk::Bytes(header.into()));
+        chunks.push(Chunk::File(range.clone()));
+    }
+
+    let trailer = format!("\r\n--{}--\r\n", boundary);
+    total += trailer.len() as u64;
+    chunks.push(Chunk::Bytes(trailer.into()));
+
+    (chunks, total)
+}
+
+/// A part of a response body.
+enum Chunk {
+    Bytes(Bytes),
+    File(Range<u64>),
+}
+
+/// Streams the chunks of a body from a blocking task.
+fn stream(file: SourceFile, chunks: Vec<Chunk>) -> Body {
+    let (mut sender, body) = Body::channel();
+    let handle = Handle::current();
+
+    tokio::task::spawn_blocking(move || {
+        let result = write(file, chunks, |bytes| send(&handle, &mut sender, bytes));
+        if result.is_err() {
+            // make sure the client doesn't mistake a truncated body for a complete one
+            sender.abort();
+        }
+    });
+
+    body
+}
+
+fn send(handle: &Handle, sender: &mut Sender, bytes: Bytes) -> io::Result<()> {
+    handle
+        .block_on(sender.send_data(bytes))
+        .map_err(|err| io::Error::new(io::ErrorKind::BrokenPipe, err))
+}
+
+/// Reads the chunks of a body from the file.
+fn write(
+    mut file: SourceFile,
+    chunks: Vec<Chunk>,
+    mut send: impl FnMut(Bytes) -> io::Result<()>,
+) -> io::Result<()> {
+    for chunk in chunks {
+        match chunk {
+            Chunk::Bytes(bytes) => send(bytes)?,
+            Chunk::File(range) => {
+                file.reader.seek(SeekFrom::Start(range.start))?;
+
+                let mut remaining = range.end - range.start;
+                while remaining > 0 {
+                    let mut buf = vec![0; remaining.min(CHUNK_SIZE) as usize];
+                    let n = file.reader.read(&mut buf)?;
+                    if n == 0 {
+                        return Err(io::ErrorKind::UnexpectedEof.into());
+                    }
+
+                    buf.truncate(n);
+                    remaining -= n as u64;
+                    send(buf.into())?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn status(status: StatusCode) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RedirectPolicy, Router};
+
+    #[test]
+    fn test_parse_range() {
+        let tests = vec![
+            ("bytes=0-4", Some(vec![(0, 5)])),
+            ("bytes=5-", Some(vec![(5, 10)])),
+            ("bytes=-3", Some(vec![(7, 10)])),
+            ("bytes=0-0, 2-3", Some(vec![(0, 1), (2, 4)])),
+            ("bytes=8-20", Some(vec![(8, 10)])),
+            ("bytes=-20", Some(vec![(0, 10)])),
+            ("bytes=10-", Some(vec![])),
+            ("bytes=4-2", None),
+            ("bytes=a-b", None),
+            ("items=0-4", None),
+            ("bytes=0-9, 0-9", None),
+        ];
+
+        for (value, expected) in tests {
+            let ranges = parse_range(value, 10)
+                .map(|ranges| ranges.iter().map(|r| (r.start, r.end)).collect::<Vec<_>>());
+            assert_eq!(ranges, expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_accepted_encodings() {
+        let tests = vec![
+            ("gzip, deflate, br", vec!["br", "gzip"]),
+            ("gzip;q=1.0, br;q=0.5", vec!["gzip", "br"]),
+            ("br;q=0, x-gzip", vec!["gzip"]),
+            ("*;q=0.2, gzip", vec!["gzip", "br"]),
+            ("identity", vec![]),
+            ("", vec![]),
+        ];
+
+        for (value, expected) in tests {
+            let req = Request::get("/")
+                .header(header::ACCEPT_ENCODING, value)
+                .body(Body::empty())
+                .unwrap();
+            let encodings = accepted_encodings(&req)
+                .into_iter()
+                .map(|(encoding, _)| encoding)
+                .collect::<Vec<_>>();
+            assert_eq!(encodings, expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_requested_path() {
+        let path = |value: &str| {
+            let mut params = Params::default();
+            params.push(FILEPATH.to_owned(), value.to_owned());
+            let mut req = Request::new(Body::empty());
+            req.extensions_mut().insert(params);
+            requested_path(&req)
+        };
+
+        assert_eq!(path("/css/app.css"), Some("css/app.css".to_owned()));
+        assert_eq!(path("/../../etc/passwd"), Some("etc/passwd".to_owned()));
+        assert_eq!(path("/../secret"), Some("secret".to_owned()));
+        assert_eq!(path("/read me.txt"), Some("read me.txt".to_owned()));
+        assert_eq!(path("/a\0b"), None);
+        assert_eq!(path("/..\\..\\secret"), None);
+    }
+
+    #[tokio::test]
+    async fn serve_directory() {
+        let root = std::env::temp_dir().join(format!("httprouter-fs-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("css")).unwrap();
+        std::fs::write(root.join("css/app.css"), "0123456789").unwrap();
+
+        let router = Router::default().serve_files("/static/*filepath", &root);
+        let get = |path: &str, headers: &[(header::HeaderName, &str)]| {
+            let mut req = Request::get(path);
+            for (name, value) in headers {
+                req = req.header(name, *value);
+            }
+            router.serve(req.body(Body::empty()).unwrap())
+        };
+
+        let res = get("/static/css/app.css", &[]).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "text/css");
+        assert_eq!(res.headers()[header::CONTENT_LENGTH], "10");
+        let modified = res.headers()[header::LAST_MODIFIED]
+            .to_str()
+            .unwrap()
+            .to_owned();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "0123456789");
+
+        let res = get(
+            "/static/css/app.css",
+            &[(header::IF_MODIFIED_SINCE, &modified)],
+        );
+        assert_eq!(res.await.unwrap().status(), StatusCode::NOT_MODIFIED);
+
+        let res = get("/static/css/app.css", &[(header::RANGE, "bytes=2-4")])
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(res.headers()[header::CONTENT_RANGE], "bytes 2-4/10");
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "234");
+
+        let res = get("/static/css/app.css", &[(header::RANGE, "bytes=0-1,-2")])
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        let content_type = res.headers()[header::CONTENT_TYPE].to_str().unwrap();
+        let boundary = content_type.split("boundary=").nth(1).unwrap().to_owned();
+        let length = res.headers()[header::CONTENT_LENGTH]
+            .to_str()
+            .unwrap()
+            .to_owned();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(length, body.len().to_string());
+        assert_eq!(
+            body,
+            format!(
+                "--{b}\r\ncontent-type: text/css\r\ncontent-range: bytes 0-1/10\r\n\r\n01\
+                 \r\n--{b}\r\ncontent-type: text/css\r\ncontent-range: bytes 8-9/10\r\n\r\n89\
+                 \r\n--{b}--\r\n",
+                b = boundary
+            )
+        );
+
+        let res = get("/static/css/app.css", &[(header::RANGE, "bytes=20-")])
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(res.headers()[header::CONTENT_RANGE], "bytes */10");
+
+        let req = Request::head("/static/css/app.css")
+            .body(Body::empty())
+            .unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.headers()[header::CONTENT_LENGTH], "10");
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert!(body.is_empty());
+
+        for path in [
+            "/static/css/",
+            "/static/missing.css",
+            "/static/%2e%2e/Cargo.toml",
+        ]
+        .iter()
+        {
+            let res = get(path, &[]).await.unwrap();
+            assert_eq!(res.status(), StatusCode::NOT_FOUND, "{}", path);
+        }
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn serve_directories() {
+        let root = std::env::temp_dir().join(format!("httprouter-dir-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("site")).unwrap();
+        std::fs::create_dir_all(root.join("artifacts/logs")).unwrap();
+        std::fs::write(root.join("site/index.html"), "<h1>Home</h1>").unwrap();
+        std::fs::write(root.join("artifacts/a&b.txt"), "ab").unwrap();
+
+        let router = Router::default()
+            .serve_files("/site/*filepath", root.join("site"))
+            .serve_files(
+                "/artifacts/*filepath",
+                StaticFiles::new(root.join("artifacts")).listing(),
+            );
+
+        let get = |path: &str, accept: &str| {
+            let req = Request::get(path)
+                .header(header::ACCEPT, accept)
+                .body(Body::empty())
+                .unwrap();
+            router.serve(req)
+        };
+
+        let res = get("/site/", "text/html").await.unwrap();
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "text/html");
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "<h1>Home</h1>");
+
+        let res = get("/artifacts/logs", "text/html").await.unwrap();
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(res.headers()[header::LOCATION], "/artifacts/logs/");
+
+        let res = get("/artifacts/a&b.txt/", "text/html").await.unwrap();
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(res.headers()[header::LOCATION], "/artifacts/a&b.txt");
+
+        let res = get("/artifacts/", "text/html").await.unwrap();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.contains("<li><a href=\"a%26b.txt\">a&amp;b.txt</a></li>"));
+        assert!(body.contains("<li><a href=\"logs/\">logs/</a></li>"));
+
+        let res = get("/artifacts/", "application/json").await.unwrap();
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "application/json");
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.starts_with("[{\"name\":\"a&b.txt\",\"dir\":false,\"size\":2,"));
+        assert!(body.contains("{\"name\":\"logs\",\"dir\":true,\"size\":0,"));
+
+        // listings are disabled by default
+        std::fs::remove_file(root.join("site/index.html")).unwrap();
+        let res = get("/site/", "text/html").await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        // the redirect policy is read at request time, even if set afterwards
+        let router = router.redirect_policy(
+            RedirectPolicy::default().trailing_slash_status(StatusCode::FOUND, StatusCode::FOUND),
+        );
+        let req = Request::get("/artifacts/logs?sort=name")
+            .body(Body::empty())
+            .unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+        assert_eq!(
+            res.headers()[header::LOCATION],
+            "/artifacts/logs/?sort=name"
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn serve_precompressed() {
+        let root = std::env::temp_dir().join(format!("httprouter-gz-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("app.css"), "plain").unwrap();
+        std::fs::write(root.join("app.css.gz"), "gzip").unwrap();
+        std::fs::write(root.join("app.css.br"), "brotli").unwrap();
+        std::fs::write(root.join("other.css"), "other").unwrap();
+
+        let files = StaticFiles::new(&root).precompressed();
+        let router = Router::default().serve_files("/*filepath", files);
+
+        let tests = [
+            ("/app.css", "gzip, br", Some("br"), "brotli"),
+            ("/app.css", "gzip", Some("gzip"), "gzip"),
+            ("/app.css", "identity", None, "plain"),
+            ("/other.css", "br", None, "other"),
+        ];
+
+        for (path, accept, encoding, expected) in tests.iter() {
+            let req = Request::get(*path)
+                .header(header::ACCEPT_ENCODING, *accept)
+                .body(Body::empty())
+                .unwrap();
+            let res = router.serve(req).await.unwrap();
+            assert_eq!(res.headers()[header::CONTENT_TYPE], "text/css");
+            assert_eq!(res.headers()[header::VARY], "accept-encoding");
+            assert_eq!(
+                res.headers()
+                    .get(header::CONTENT_ENCODING)
+                    .map(|value| value.to_str().unwrap()),
+                *encoding
+            );
+            let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            assert_eq!(body, *expected);
+        }
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn serve_memory_source() {
+        let source = MemorySource::new()
+            .insert("index.html", "<h1>Home</h1>")
+            .insert("css/app.css", "0123456789");
+        let router = Router::default().serve_files("/*filepath", StaticFiles::from_source(source));
+
+        let get = |path: &str, headers: &[(header::HeaderName, &str)]| {
+            let mut req = Request::get(path);
+            for (name, value) in headers {
+                req = req.header(name, *value);
+            }
+            router.serve(req.body(Body::empty()).unwrap())
+        };
+
+        let res = get("/", &[]).await.unwrap();
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "text/html");
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "<h1>Home</h1>");
+
+        let res = get("/css/app.css", &[]).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let etag = res.headers()[header::ETAG].to_str().unwrap().to_owned();
+        assert!(etag.starts_with('"') && etag.ends_with('"'));
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "0123456789");
+
+        let res = get("/css/app.css", &[(header::IF_NONE_MATCH, &etag)]);
+        assert_eq!(res.await.unwrap().status(), StatusCode::NOT_MODIFIED);
+
+        let weak = format!("\"other\", W/{}", etag);
+        let res = get("/css/app.css", &[(header::IF_NONE_MATCH, &weak)]);
+        assert_eq!(res.await.unwrap().status(), StatusCode::NOT_MODIFIED);
+
+        let res = get("/css/app.css", &[(header::IF_NONE_MATCH, "\"other\"")]);
+        assert_eq!(res.await.unwrap().status(), StatusCode::OK);
+
+        let res = get(
+            "/css/app.css",
+            &[(header::RANGE, "bytes=2-4"), (header::IF_RANGE, &etag)],
+        )
+        .await
+        .unwrap();
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "234");
+
+        let res = get(
+            "/css/app.css",
+            &[
+                (header::RANGE, "bytes=2-4"),
+                (header::IF_RANGE, "\"stale\""),
+            ],
+        );
+        assert_eq!(res.await.unwrap().status(), StatusCode::OK);
+
+        let res = get("/css", &[]).await.unwrap();
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(res.headers()[header::LOCATION], "/css/");
+
+        let res = get("/css/missing.css", &[]).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn spa_fallback() {
+        let files = StaticFiles::from_source(
+            MemorySource::new()
+                .insert("index.html", "<div id=\"app\"></div>")
+                .insert("app.js", "render()"),
+        );
+
+        let router = Router::default()
+            .get(
+                "/api/users",
+                crate::handler_fn(|_| async {
+                    Ok::<_, Infallible>(Response::new(Body::from("[]")))
+                }),
+            )
+            .serve_files("/static/*filepath", files.clone())
+            .spa_fallback(files);
+
+        let request = |method: Method, path: &str, accept: &str| {
+            let req = Request::builder()
+                .method(method)
+                .uri(path)
+                .header(header::ACCEPT, accept)
+                .body(Body::empty())
+                .unwrap();
+            router.serve(req)
+        };
+
+        let html = "text/html,application/xhtml+xml,*/*;q=0.8";
+        for path in ["/", "/dashboard", "/dashboard/settings/"].iter() {
+            let res = request(Method::GET, path, html).await.unwrap();
+            assert_eq!(res.status(), StatusCode::OK, "{}", path);
+            assert_eq!(res.headers()[header::CONTENT_TYPE], "text/html");
+            let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            assert_eq!(body, "<div id=\"app\"></div>");
+        }
+
+        let res = request(Method::GET, "/api/users", html).await.unwrap();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "[]");
+
+        // missing assets are not answered with the page
+        let res = request(Method::GET, "/static/missing.js", html)
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let res = request(Method::GET, "/missing.js", "*/*").await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let res = request(Method::GET, "/dashboard", "text/html;q=0")
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        // redirects and 405 responses take precedence
+        let res = request(Method::GET, "/API/users", html).await.unwrap();
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(res.headers()[header::LOCATION], "/api/users");
+        let res = request(Method::POST, "/api/users", html).await.unwrap();
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        let res = request(Method::POST, "/dashboard", html).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn serve_fingerprinted() {
+        let source = MemorySource::new().insert("js/app.js", "render()");
+        let files = StaticFiles::from_source(source).fingerprint().unwrap();
+        let url = format!(
+            "/static/{}",
+            files.manifest().unwrap().asset_url("js/app.js").unwrap()
+        );
+        assert_ne!(url, "/static/js/app.js");
+
+        let router = Router::default().serve_files("/static/*filepath", files);
+        let get = |path: &str| router.serve(Request::get(path).body(Body::empty()).unwrap());
+
+        let res = get(&url).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "text/javascript");
+        assert_eq!(res.headers()[header::CACHE_CONTROL], CACHE_IMMUTABLE);
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "render()");
+
+        let res = get("/static/js/app.js").await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CACHE_CONTROL], CACHE_SHORT);
+
+        let res = get("/static/js/app.00000000.js").await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,421 @@
+//! The sources that static files are served from.
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::{self, Cursor, Read, Seek};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use hyper::body::Bytes;
+
+/// A source of static files, such as a directory on disk or an in-memory map.
+///
+/// Paths are relative to the root of the source, separated by `/`, and never contain
+/// empty, `.` or `..` segments. The root itself is the empty path.
+pub trait FileSource: Send + Sync + 'static {
+    /// Opens the file or directory at the given path.
+    ///
+    /// Missing entries should be reported with [`io::ErrorKind::NotFound`].
+    fn open(&self, path: &str) -> io::Result<SourceEntry>;
+
+    /// Returns the entries of the directory at the given path, used to render
+    /// directory listings. Sources that don't support listings can rely on the
+    /// default implementation, which reports every directory as missing.
+    fn read_dir(&self, path: &str) -> io::Result<Vec<DirEntry>> {
+        let _ = path;
+        Err(io::ErrorKind::NotFound.into())
+    }
+
+    /// Whether the methods of the source block the current thread, in which case they
+    /// are called, and files are read, on a thread where blocking is acceptable.
+    fn blocking(&self) -> bool {
+        true
+    }
+}
+
+/// An entry opened by a [`FileSource`].
+pub enum SourceEntry {
+    File(SourceFile),
+    Directory,
+}
+
+/// A readable file, along with its metadata.
+pub struct SourceFile {
+    pub(crate) reader: Box<dyn ReadSeek>,
+    pub(crate) len: u64,
+    pub(crate) modified: Option<SystemTime>,
+    pub(crate) etag: Option<String>,
+    // whether range requests are answered, which seek within the reader
+    pub(crate) ranges: bool,
+}
+
+/// A reader that can be sent to a blocking thread.
+pub(crate) trait ReadSeek: Read + Seek + Send {}
+
+impl<T> ReadSeek for T where T: Read + Seek + Send {}
+
+impl SourceFile {
+    /// Creates a file with the given contents and length in bytes.
+    pub fn new(reader: impl Read + Seek + Send + 'static, len: u64) -> Self {
+        Self {
+            reader: Box::new(reader),
+            len,
+            modified: None,
+            etag: None,
+            ranges: true,
+        }
+    }
+
+    /// Sets the time the file was last modified, sent as its `Last-Modified` date.
+    pub fn modified(mut self, modified: SystemTime) -> Self {
+        self.modified = Some(modified);
+        self
+    }
+
+    /// Sets the entity tag of the file, without quotes. The tag must change whenever
+    /// the contents of the file change.
+    pub fn etag(mut self, etag: impl Into<String>) -> Self {
+        self.etag = Some(etag.into());
+        self
+    }
+
+    /// Answers range requests with the whole file, for readers that can't seek.
+    #[cfg(feature = "zip")]
+    pub(crate) fn without_ranges(mut self) -> Self {
+        self.ranges = false;
+        self
+    }
+}
+
+impl fmt::Debug for SourceFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SourceFile")
+            .field("len", &self.len)
+            .field("modified", &self.modified)
+            .field("etag", &self.etag)
+            .finish()
+    }
+}
+
+/// An entry of a directory, see [`FileSource::read_dir`].
+#[derive(Clone, Debug)]
+pub struct DirEntry {
+    pub(crate) name: String,
+    pub(crate) is_dir: bool,
+    pub(crate) len: u64,
+    pub(crate) modified: Option<SystemTime>,
+}
+
+impl DirEntry {
+    /// A file with the given name and length in bytes.
+    pub fn file(name: impl Into<String>, len: u64) -> Self {
+        Self {
+            name: name.into(),
+            is_dir: false,
+            len,
+            modified: None,
+        }
+    }
+
+    /// A directory with the given name.
+    pub fn dir(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            is_dir: true,
+            len: 0,
+            modified: None,
+        }
+    }
+
+    /// Sets the time the entry was last modified.
+    pub fn modified(mut self, modified: SystemTime) -> Self {
+        self.modified = Some(modified);
+        self
+    }
+}
+
+/// Files in a directory on disk.
+#[derive(Clone, Debug)]
+pub struct DiskSource {
+    root: PathBuf,
+}
+
+impl DiskSource {
+    /// Serve the files in the given root directory.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Returns the path of the entry on disk.
+    fn resolve(&self, path: &str) -> io::Result<PathBuf> {
+        let root = self.root.canonicalize()?;
+        let resolved = root.join(Path::new(path)).canonicalize()?;
+
+        // symbolic links must not point outside of the root
+        if !resolved.starts_with(&root) {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+
+        Ok(resolved)
+    }
+}
+
+impl FileSource for DiskSource {
+    fn open(&self, path: &str) -> io::Result<SourceEntry> {
+        let file = File::open(self.resolve(path)?)?;
+        let meta = file.metadata()?;
+        if meta.is_dir() {
+            return Ok(SourceEntry::Directory);
+        }
+
+        let len = meta.len();
+        let mut file = SourceFile::new(file, len);
+        if let Ok(modified) = meta.modified() {
+            let secs = modified
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_secs())
+                .unwrap_or_default();
+
+            file = file
+                .modified(modified)
+                .etag(format!("{:x}-{:x}", secs, len));
+        }
+
+        Ok(SourceEntry::File(file))
+    }
+
+    fn read_dir(&self, path: &str) -> io::Result<Vec<DirEntry>> {
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(self.resolve(path)?)? {
+            let entry = entry?;
+            // skip entries that can't be represented, or are broken symbolic links
+            let (name, meta) = match (
+                entry.file_name().into_string(),
+                std::fs::metadata(entry.path()),
+            ) {
+                (Ok(name), Ok(meta)) => (name, meta),
+                _ => continue,
+            };
+
+            let mut entry = if meta.is_dir() {
+                DirEntry::dir(name)
+            } else {
+                DirEntry::file(name, meta.len())
+            };
+
+            if let Ok(modified) = meta.modified() {
+                entry = entry.modified(modified);
+            }
+
+            entries.push(entry);
+        }
+
+        Ok(entries)
+    }
+}
+
+/// Files held in memory, such as assets embedded in the binary with `include_bytes!`.
+///
+/// Entity tags are computed from the contents of each file when it is inserted, and
+/// directories are implied by the paths of the files.
+/// ```rust
+/// use httprouter::{MemorySource, Router, StaticFiles};
+///
+/// let source = MemorySource::new()
+///     .insert("index.html", "<h1>Hello, World!</h1>")
+///     .insert("css/app.css", &b"body { margin: 0 }"[..]);
+///
+/// let router = Router::default().serve_files("/*filepath", StaticFiles::from_source(source));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MemorySource {
+    files: BTreeMap<String, (Bytes, String)>,
+    modified: Option<SystemTime>,
+}
+
+impl MemorySource {
+    /// Creates an empty source.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file at the given path, relative to the root of the source.
+    pub fn insert(mut self, path: impl AsRef<str>, contents: impl Into<Bytes>) -> Self {
+        let path = path.as_ref().trim_matches('/').to_owned();
+        let contents = contents.into();
+        let etag = format!("{:016x}", fnv1a(&contents));
+        self.files.insert(path, (contents, etag));
+        self
+    }
+
+    /// Sets the `Last-Modified` date of every file, such as the time the binary was built.
+    pub fn modified(mut self, modified: SystemTime) -> Self {
+        self.modified = Some(modified);
+        self
+    }
+}
+
+impl FileSource for MemorySource {
+    fn open(&self, path: &str) -> io::Result<SourceEntry> {
+        if let Some((contents, etag)) = self.files.get(path) {
+            let mut file = SourceFile::new(Cursor::new(contents.clone()), contents.len() as u64)
+                .etag(etag.clone());
+            if let Some(modified) = self.modified {
+                file = file.modified(modified);
+            }
+
+            return Ok(SourceEntry::File(file));
+        }
+
+        open_dir(&self.files, path)
+    }
+
+    fn read_dir(&self, path: &str) -> io::Result<Vec<DirEntry>> {
+        let entries = read_dir(&self.files, path, |name, (contents, _)| {
+            DirEntry::file(name, contents.len() as u64)
+        })?;
+
+        Ok(match self.modified {
+            Some(modified) => entries
+                .into_iter()
+                .map(|entry| entry.modified(modified))
+                .collect(),
+            None => entries,
+        })
+    }
+
+    fn blocking(&self) -> bool {
+        false
+    }
+}
+
+/// Returns the files under the directory at the given path of a map of files, with
+/// paths relative to the directory.
+fn children<'a, V>(
+    files: &'a BTreeMap<String, V>,
+    path: &str,
+) -> impl Iterator<Item = (&'a str, &'a V)> {
+    let prefix = if path.is_empty() {
+        String::new()
+    } else {
+        [path, "/"].concat()
+    };
+
+    let len = prefix.len();
+    files
+        .range(prefix.clone()..)
+        .take_while(move |(name, _)| name.starts_with(&prefix))
+        .map(move |(name, value)| (&name[len..], value))
+}
+
+/// Opens the directory at the given path of a map of files, whose directories are
+/// implied by the paths of the files.
+pub(crate) fn open_dir<V>(files: &BTreeMap<String, V>, path: &str) -> io::Result<SourceEntry> {
+    match children(files, path).next() {
+        Some(_) => Ok(SourceEntry::Directory),
+        None => Err(io::ErrorKind::NotFound.into()),
+    }
+}
+
+/// Returns the entries of the directory at the given path of a map of files, whose
+/// directories are implied by the paths of the files.
+pub(crate) fn read_dir<V>(
+    files: &BTreeMap<String, V>,
+    path: &str,
+    file: impl Fn(&str, &V) -> DirEntry,
+) -> io::Result<Vec<DirEntry>> {
+    let mut entries = Vec::<DirEntry>::new();
+    for (name, value) in children(files, path) {
+        let entry = match name.split_once('/') {
+            Some((dir, _)) if entries.last().map(|entry| entry.name.as_str()) == Some(dir) => {
+                continue
+            }
+            Some((dir, _)) => DirEntry::dir(dir),
+            None => file(name, value),
+        };
+
+        entries.push(entry);
+    }
+
+    if entries.is_empty() {
+        return Err(io::ErrorKind::NotFound.into());
+    }
+
+    Ok(entries)
+}
+
+/// The 64-bit FNV-1a hash of the given bytes.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hasher = Fnv1a::default();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+/// A 64-bit FNV-1a hasher, for hashing contents that are read in chunks.
+pub(crate) struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0 = bytes.iter().fold(self.0, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+        });
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_source() {
+        let source = MemorySource::new()
+            .insert("index.html", "index")
+            .insert("/css/app.css", "app")
+            .insert("css/vendor/reset.css", "reset")
+            .insert("cssx", "not a child of css");
+
+        assert!(matches!(source.open(""), Ok(SourceEntry::Directory)));
+        assert!(matches!(source.open("css"), Ok(SourceEntry::Directory)));
+        assert!(matches!(
+            source.open("css/app.css"),
+            Ok(SourceEntry::File(_))
+        ));
+        assert!(source.open("css/missing.css").is_err());
+
+        let names = |path| {
+            source
+                .read_dir(path)
+                .unwrap()
+                .into_iter()
+                .map(|entry| (entry.name, entry.is_dir))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names("css"),
+            [("app.css".to_owned(), false), ("vendor".to_owned(), true)]
+        );
+        assert_eq!(
+            names(""),
+            [
+                ("css".to_owned(), true),
+                ("cssx".to_owned(), false),
+                ("index.html".to_owned(), false)
+            ]
+        );
+
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
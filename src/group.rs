@@ -0,0 +1,203 @@
+use crate::layer::BoxLayer;
+use crate::path::join;
+use crate::router::RouteEntry;
+use crate::{Handler, HandlerError, HandlerFuture, HandlerService};
+
+use std::error::Error as StdError;
+
+use hyper::service::Service;
+use hyper::{Body, Method, Request, Response};
+use tower_layer::Layer;
+
+/// A group of routes sharing a common path prefix, created with
+/// [`Router::group`](crate::Router::group).
+pub struct Group {
+    prefix: String,
+    routes: Vec<RouteEntry>,
+    layers: Vec<BoxLayer>,
+}
+
+impl Group {
+    pub(crate) fn new(prefix: String) -> Self {
+        Self {
+            prefix,
+            routes: Vec::new(),
+            layers: Vec::new(),
+        }
+    }
+
+    pub(crate) fn into_routes(self) -> Vec<RouteEntry> {
+        let layers = self.layers;
+        self.routes
+            .into_iter()
+            .map(|mut route| {
+                route.layers.extend(layers.iter().cloned());
+                route
+            })
+            .collect()
+    }
+
+    /// Wrap every route of the group, including routes of nested groups and routes
+    /// registered after the layer is added, with a [`tower::Layer`], such as an
+    /// authentication middleware for an admin area. The layer is stored with each
+    /// handler in the routing tree, so routes outside of the group and unmatched
+    /// requests are unaffected.
+    /// ```rust
+    /// use httprouter::{Router, handler_fn};
+    /// use hyper::{Response, Body};
+    /// use std::convert::Infallible;
+    /// use std::time::Duration;
+    /// use tower::timeout::TimeoutLayer;
+    ///
+    /// let handler = handler_fn(|_| async {
+    ///     Ok::<_, Infallible>(Response::new(Body::empty()))
+    /// });
+    ///
+    /// let router = Router::default()
+    ///     .get("/", handler.clone())
+    ///     .group("/admin", |g| {
+    ///         g.layer(TimeoutLayer::new(Duration::from_secs(5)))
+    ///             .get("/users", handler.clone())
+    ///             .delete("/users/:id", handler)
+    ///     });
+    /// ```
+    ///
+    /// [`tower::Layer`]: https://docs.rs/tower/latest/tower/trait.Layer.html
+    pub fn layer<L>(mut self, layer: L) -> Self
+    where
+        L: Layer<Handler> + Send + Sync + 'static,
+        L::Service:
+            Service<Request<Body>, Response = Response<Body>> + Clone + Send + Sync + 'static,
+        <L::Service as Service<Request<Body>>>::Future: Send + Sync + 'static,
+        <L::Service as Service<Request<Body>>>::Error: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        self.layers.push(BoxLayer::new(layer));
+        self
+    }
+
+    /// Register a handler for the given path, relative to the group prefix, and method.
+    pub fn handle<H, F, E>(mut self, path: impl AsRef<str>, method: Method, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        let path = join(&self.prefix, path.as_ref());
+        self.routes.push(RouteEntry::new(method, path, handler));
+        self
+    }
+
+    /// Register a named handler for the given path, relative to the group prefix, and
+    /// method. See [`Router::handle_named`](crate::Router::handle_named).
+    pub fn handle_named<H, F, E>(
+        mut self,
+        name: impl Into<String>,
+        path: impl AsRef<str>,
+        method: Method,
+        handler: H,
+    ) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        let path = join(&self.prefix, path.as_ref());
+        let mut route = RouteEntry::new(method, path, handler);
+        route.name = Some(name.into());
+        self.routes.push(route);
+        self
+    }
+
+    /// Register a handler for the given path, relative to the group prefix, that
+    /// matches requests of any method. See [`Router::any`](crate::Router::any).
+    pub fn any<H, F, E>(mut self, path: impl AsRef<str>, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        let path = join(&self.prefix, path.as_ref());
+        self.routes.push(RouteEntry::any(path, handler));
+        self
+    }
+
+    /// Register a nested group of routes, with a prefix relative to this group's prefix.
+    pub fn group<F>(mut self, prefix: impl AsRef<str>, f: F) -> Self
+    where
+        F: FnOnce(Group) -> Group,
+    {
+        let group = f(Group::new(join(&self.prefix, prefix.as_ref())));
+        self.routes.extend(group.into_routes());
+        self
+    }
+
+    /// Register a handler for `GET` requests
+    pub fn get<H, F, E>(self, path: impl AsRef<str>, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.handle(path, Method::GET, handler)
+    }
+
+    /// Register a handler for `HEAD` requests
+    pub fn head<H, F, E>(self, path: impl AsRef<str>, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.handle(path, Method::HEAD, handler)
+    }
+
+    /// Register a handler for `OPTIONS` requests
+    pub fn options<H, F, E>(self, path: impl AsRef<str>, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.handle(path, Method::OPTIONS, handler)
+    }
+
+    /// Register a handler for `POST` requests
+    pub fn post<H, F, E>(self, path: impl AsRef<str>, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.handle(path, Method::POST, handler)
+    }
+
+    /// Register a handler for `PUT` requests
+    pub fn put<H, F, E>(self, path: impl AsRef<str>, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.handle(path, Method::PUT, handler)
+    }
+
+    /// Register a handler for `PATCH` requests
+    pub fn patch<H, F, E>(self, path: impl AsRef<str>, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.handle(path, Method::PATCH, handler)
+    }
+
+    /// Register a handler for `DELETE` requests
+    pub fn delete<H, F, E>(self, path: impl AsRef<str>, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.handle(path, Method::DELETE, handler)
+    }
+}
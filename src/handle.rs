@@ -0,0 +1,300 @@
+//! Modifying the routes of a [`Router`] while it is being served.
+use crate::router::{nested_path, RouteEntry};
+use crate::{HandlerError, HandlerFuture, HandlerService, RouteError, Router};
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+use arc_swap::ArcSwap;
+use hyper::Method;
+
+/// A handle to a [`Router`] that is being served, created with
+/// [`Router::into_service_with_handle`](crate::Router::into_service_with_handle).
+///
+/// Every modification builds a new routing table and atomically swaps it in, so
+/// requests that are already being handled are unaffected, and route lookups never
+/// wait for a modification to complete. Handles are cheap to clone, and can be
+/// shared between tasks.
+/// ```rust
+/// use httprouter::{Router, handler_fn};
+/// use hyper::{Response, Body, Method};
+/// use std::convert::Infallible;
+///
+/// let handler = handler_fn(|_| async {
+///     Ok::<_, Infallible>(Response::new(Body::empty()))
+/// });
+///
+/// let router = Router::default().get("/admin", handler.clone());
+/// let (service, handle) = router.into_service_with_handle();
+///
+/// handle.add("/tenants/acme", Method::GET, handler.clone()).unwrap();
+/// handle.replace("/admin", Method::GET, handler).unwrap();
+/// assert!(handle.remove("/tenants/acme", Method::GET).unwrap());
+/// ```
+#[derive(Clone)]
+pub struct RouterHandle {
+    router: Arc<ArcSwap<Router>>,
+    // serializes modifications, so that concurrent updates are not lost
+    lock: Arc<Mutex<()>>,
+}
+
+impl RouterHandle {
+    pub(crate) fn new(router: Arc<ArcSwap<Router>>) -> Self {
+        Self {
+            router,
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Register a handler for the given path and method, returning an error if the
+    /// route could not be registered. See [`Router::try_handle`].
+    pub fn add<H, F, E>(
+        &self,
+        path: impl Into<String>,
+        method: Method,
+        handler: H,
+    ) -> Result<(), RouteError>
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        let route = RouteEntry::new(method, path.into(), handler);
+        self.update(|routes| routes.push(route), |router| router)
+    }
+
+    /// Replace the handler registered for the given path and method, or register it
+    /// if there is none.
+    pub fn replace<H, F, E>(
+        &self,
+        path: impl Into<String>,
+        method: Method,
+        handler: H,
+    ) -> Result<(), RouteError>
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        let route = RouteEntry::new(method, path.into(), handler);
+        self.update(
+            |routes| match routes
+                .iter_mut()
+                .find(|r| r.method == route.method && r.path == route.path)
+            {
+                Some(existing) => {
+                    existing.service = route.service;
+                }
+                None => routes.push(route),
+            },
+            |router| router,
+        )
+    }
+
+    /// Remove the handler registered for the given path and method. Returns `false`
+    /// if no such handler was registered.
+    pub fn remove(&self, path: impl AsRef<str>, method: Method) -> Result<bool, RouteError> {
+        let path = path.as_ref();
+        self.remove_where(|r| r.method.as_ref() == Some(&method) && r.path == path)
+    }
+
+    /// Remove the handler registered for the given path with [`Router::any`]. Returns
+    /// `false` if no such handler was registered.
+    pub fn remove_any(&self, path: impl AsRef<str>) -> Result<bool, RouteError> {
+        let path = path.as_ref();
+        self.remove_where(|r| r.method.is_none() && r.path == path)
+    }
+
+    /// Remove the router or service mounted under the given path prefix with
+    /// [`Router::nest`] or [`Router::nest_service`]. Returns `false` if nothing was
+    /// mounted under the prefix.
+    pub fn remove_nested(&self, prefix: impl AsRef<str>) -> Result<bool, RouteError> {
+        let path = nested_path(prefix.as_ref());
+        self.remove_where(|r| r.method.is_none() && r.path == path)
+    }
+
+    /// Removes the routes that match the predicate, returning whether any did.
+    fn remove_where(&self, remove: impl Fn(&RouteEntry) -> bool) -> Result<bool, RouteError> {
+        let mut removed = false;
+        self.update(
+            |routes| {
+                let len = routes.len();
+                routes.retain(|r| !remove(r));
+                removed = routes.len() != len;
+            },
+            |router| router,
+        )?;
+
+        Ok(removed)
+    }
+
+    /// Replace the `NotFound` handler. See [`Router::not_found`].
+    pub fn not_found<H, F, E>(&self, handler: H)
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        // rebuilding with the same routes cannot fail
+        let _ = self.update(|_| (), |router| router.not_found(handler));
+    }
+
+    /// Replace the `MethodNotAllowed` handler. See [`Router::method_not_allowed`].
+    pub fn method_not_allowed<H, F, E>(&self, handler: H)
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        let _ = self.update(|_| (), |router| router.method_not_allowed(handler));
+    }
+
+    /// Remove the `NotFound` handler, so that unmatched requests are answered by the
+    /// router itself again.
+    pub fn remove_not_found(&self) {
+        let _ = self.update(|_| (), Router::without_not_found);
+    }
+
+    /// Remove the `MethodNotAllowed` handler, so that requests with a method that is
+    /// not allowed are answered by the router itself again.
+    pub fn remove_method_not_allowed(&self) {
+        let _ = self.update(|_| (), Router::without_method_not_allowed);
+    }
+
+    /// Builds a new router from the routes of the current one, and swaps it in.
+    fn update(
+        &self,
+        routes: impl FnOnce(&mut Vec<RouteEntry>),
+        configure: impl FnOnce(Router) -> Router,
+    ) -> Result<(), RouteError> {
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let current = self.router.load();
+        let mut entries = current.entries().to_vec();
+        routes(&mut entries);
+
+        let router = configure(current.rebuild(entries)?);
+        self.router.store(Arc::new(router));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handler_fn;
+    use std::convert::Infallible;
+
+    use hyper::service::Service;
+    use hyper::{Body, Request, Response, StatusCode};
+
+    #[tokio::test]
+    async fn modify_live_router() {
+        let echo = |body: &'static str| {
+            handler_fn(move |_| async move { Ok::<_, Infallible>(Response::new(Body::from(body))) })
+        };
+
+        let router = Router::default().get("/a", echo("a"));
+        let (mut make_service, handle) = router.into_service_with_handle();
+        let mut service = make_service.call(()).await.unwrap();
+
+        let mut get = |path: &'static str| {
+            let req = Request::get(path).body(Body::empty()).unwrap();
+            service.call(req)
+        };
+
+        // a request that is already in flight is not affected by the modification
+        let in_flight = get("/a");
+        handle.replace("/a", Method::GET, echo("replaced")).unwrap();
+        handle.add("/b", Method::GET, echo("b")).unwrap();
+
+        let body = hyper::body::to_bytes(in_flight.await.unwrap().into_body()).await;
+        assert_eq!(body.unwrap(), "a");
+
+        let body = hyper::body::to_bytes(get("/a").await.unwrap().into_body()).await;
+        assert_eq!(body.unwrap(), "replaced");
+
+        let body = hyper::body::to_bytes(get("/b").await.unwrap().into_body()).await;
+        assert_eq!(body.unwrap(), "b");
+
+        assert!(matches!(
+            handle.add("/b", Method::GET, echo("b")),
+            Err(RouteError::Duplicate { .. })
+        ));
+
+        assert!(handle.remove("/b", Method::GET).unwrap());
+        assert!(!handle.remove("/b", Method::GET).unwrap());
+
+        handle.not_found(handler_fn(|_| async {
+            Ok::<_, Infallible>(
+                Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        }));
+
+        let res = get("/b").await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn remove_any_nested_and_fallbacks() {
+        let echo = |body: &'static str| {
+            handler_fn(move |_| async move { Ok::<_, Infallible>(Response::new(Body::from(body))) })
+        };
+
+        let router = Router::default()
+            .any("/proxy/*path", echo("proxy"))
+            .nest("/api", Router::default().get("/", echo("api")))
+            .nest_service("/legacy", echo("legacy"))
+            .not_found(echo("not found"))
+            .method_not_allowed(echo("not allowed"));
+        let (mut make_service, handle) = router.into_service_with_handle();
+        let mut service = make_service.call(()).await.unwrap();
+
+        let mut get = |path: &'static str| {
+            let req = Request::get(path).body(Body::empty()).unwrap();
+            service.call(req)
+        };
+
+        assert!(!handle.remove("/proxy/*path", Method::GET).unwrap());
+        assert!(handle.remove_any("/proxy/*path").unwrap());
+        assert!(handle.remove_nested("/api").unwrap());
+        assert!(handle.remove_nested("/legacy/").unwrap());
+        assert!(!handle.remove_nested("/legacy").unwrap());
+
+        for path in ["/proxy/a", "/api/", "/legacy/a"].iter() {
+            let body = hyper::body::to_bytes(get(path).await.unwrap().into_body()).await;
+            assert_eq!(body.unwrap(), "not found", "{}", path);
+        }
+
+        handle.remove_not_found();
+        handle.remove_method_not_allowed();
+        let res = get("/api/").await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(hyper::body::to_bytes(res.into_body()).await.unwrap(), "");
+
+        handle.add("/a", Method::POST, echo("a")).unwrap();
+        let res = get("/a").await.unwrap();
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(hyper::body::to_bytes(res.into_body()).await.unwrap(), "");
+    }
+
+    #[test]
+    fn modify_keeps_nested_routers() {
+        let ok = handler_fn(|_| async { Ok::<_, Infallible>(Response::new(Body::empty())) });
+        let users = Router::default().handle_named("user", "/users/:id", Method::GET, ok.clone());
+
+        let router = Router::default().nest("/api", users);
+        let (_, handle) = router.into_service_with_handle();
+
+        handle.add("/health", Method::GET, ok).unwrap();
+
+        let router = handle.router.load();
+        assert_eq!(
+            router.url_for("user", &[("id", "1")]).unwrap(),
+            "/api/users/1"
+        );
+        assert_eq!(router.routes().count(), 2);
+    }
+}
@@ -0,0 +1,368 @@
+//! Routing requests to different [`Router`]s based on the requested host.
+use crate::router::{HandlerServiceImpl, Params, ResponseFut, ResponseFutKind, StoredService};
+use crate::{
+    BoxError, BuiltinResponse, HandlerError, HandlerFuture, HandlerService, RouteError, Router,
+};
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures_util::future;
+use hyper::service::Service;
+use hyper::{header, Body, Request, Response};
+use matchit::Node;
+
+/// The name of the catch-all parameter that captures an anonymous `*` wildcard.
+const WILDCARD: &str = "__wildcard";
+
+/// A router that dispatches requests to a [`Router`] based on the requested host.
+///
+/// Host patterns are matched label by label, and support the same parameters as
+/// route paths:
+/// ```text
+///  Pattern               Host
+///  example.com           example.com
+///  :tenant.example.com   acme.example.com        tenant="acme"
+///  *.example.com         a.b.example.com
+///  *sub.example.com      a.b.example.com         sub="a.b"
+/// ```
+///
+/// Matching ignores the port and the case of the host. Exact hosts take priority
+/// over patterns, and captured host parameters are available through the
+/// [`Params`] of the request, alongside the path parameters.
+/// ```rust,no_run
+/// use httprouter::{HostRouter, Params, Router, handler_fn};
+/// use hyper::{Request, Response, Body, Error};
+///
+/// async fn tenant(req: Request<Body>) -> Result<Response<Body>, Error> {
+///     let params = req.extensions().get::<Params>().unwrap();
+///     Ok(Response::new(format!("Hello, {}", params.get("tenant").unwrap()).into()))
+/// }
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let router = HostRouter::default()
+///         .host("example.com", Router::default())
+///         .host(":tenant.example.com", Router::default().get("/", handler_fn(tenant)));
+///
+///     hyper::Server::bind(&([127, 0, 0, 1], 3000).into())
+///         .serve(router.into_service())
+///         .await;
+/// }
+/// ```
+#[derive(Default)]
+pub struct HostRouter {
+    exact: HashMap<String, Arc<Router>>,
+    patterns: Node<HostRoute>,
+    registered: Vec<(String, String)>,
+    fallback: Option<Box<dyn StoredService>>,
+}
+
+struct HostRoute {
+    router: Arc<Router>,
+    // the name of the catch-all parameter, whose labels must be reversed
+    catch_all: Option<String>,
+}
+
+impl HostRouter {
+    /// Register a router for the given host pattern.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the host pattern could not be registered. See [`HostRouter::try_host`]
+    /// for a non-panicking version.
+    pub fn host(mut self, pattern: impl AsRef<str>, router: Router) -> Self {
+        if let Err(err) = self.try_host(pattern, router) {
+            panic!("{}", err);
+        }
+
+        self
+    }
+
+    /// Register a router for the given host pattern, returning an error if the
+    /// pattern could not be registered.
+    pub fn try_host(
+        &mut self,
+        pattern: impl AsRef<str>,
+        router: Router,
+    ) -> Result<&mut Self, RouteError> {
+        let pattern = pattern.as_ref();
+        let host = strip_port(pattern);
+        if host.is_empty() {
+            return Err(RouteError::InvalidPath {
+                path: pattern.to_owned(),
+                reason: "host must not be empty".into(),
+            });
+        }
+
+        let path = pattern_to_path(host).map_err(|reason| RouteError::InvalidPath {
+            path: pattern.to_owned(),
+            reason: reason.into(),
+        })?;
+
+        if let Some((with, _)) = self.registered.iter().find(|(_, p)| *p == path) {
+            return Err(RouteError::Conflict {
+                path: pattern.to_owned(),
+                with: with.clone(),
+            });
+        }
+
+        let router = Arc::new(router);
+        if !path.contains(&[':', '*'][..]) {
+            self.exact.insert(path.clone(), router);
+        } else {
+            let catch_all = path
+                .rsplit('/')
+                .next()
+                .and_then(|label| label.strip_prefix('*'))
+                .map(ToOwned::to_owned);
+
+            self.patterns
+                .insert(path.clone(), HostRoute { router, catch_all })
+                .map_err(|err| RouteError::from_insert(pattern.to_owned(), err))?;
+        }
+
+        self.registered.push((pattern.to_owned(), path));
+        Ok(self)
+    }
+
+    /// Configurable handler which is called for requests with a missing host, or
+    /// a host that doesn't match any registered pattern. By default, such requests
+    /// are answered with `404 Not Found`.
+    pub fn fallback<H, F, E>(mut self, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.fallback = Some(Box::new(HandlerServiceImpl::new(handler)));
+        self
+    }
+
+    /// Converts the `HostRouter` into a `Service` which you can serve directly with `Hyper`.
+    pub fn into_service(self) -> MakeHostRouterService {
+        MakeHostRouterService(HostRouterService(Arc::new(self)))
+    }
+
+    /// An asynchronous function from a `Request` to a `Response`. See
+    /// [`Router::serve`](crate::Router::serve).
+    pub fn serve(&self, mut req: Request<Body>) -> ResponseFut {
+        let host = request_host(&req).map(host_to_path);
+
+        if let Some(host) = host {
+            if let Some(router) = self.exact.get(&host) {
+                return router.serve(req);
+            }
+
+            if let Ok(lookup) = self.patterns.at(&host) {
+                let route = lookup.value;
+                let mut params = Params::default();
+                for (key, value) in lookup.params.iter() {
+                    if Some(key) == route.catch_all.as_deref() {
+                        if key != WILDCARD {
+                            params.push(key.to_owned(), path_to_host(value));
+                        }
+                    } else {
+                        params.push(key.to_owned(), value.to_owned());
+                    }
+                }
+
+                req.extensions_mut().insert(params);
+                return route.router.serve(req);
+            }
+        }
+
+        match self.fallback {
+            Some(ref handler) => ResponseFutKind::Boxed(handler.clone().call(req)).into(),
+            None => ResponseFutKind::Ready(Some(BuiltinResponse::NotFound.into_response())).into(),
+        }
+    }
+}
+
+/// Returns the host of the request, from either the URI or the `Host` header.
+fn request_host(req: &Request<Body>) -> Option<&str> {
+    req.uri().host().or_else(|| {
+        req.headers()
+            .get(header::HOST)
+            .and_then(|host| host.to_str().ok())
+    })
+}
+
+/// Removes the port from a host, if any.
+fn strip_port(host: &str) -> &str {
+    match host.rfind(':') {
+        Some(i) if i + 1 < host.len() && host[i + 1..].bytes().all(|b| b.is_ascii_digit()) => {
+            &host[..i]
+        }
+        _ => host,
+    }
+}
+
+/// Converts a host into a path of its labels in reverse order, such that
+/// `api.example.com:80` becomes `/com/example/api`.
+fn host_to_path(host: &str) -> String {
+    let host = strip_port(host).trim_end_matches('.').to_ascii_lowercase();
+    host.rsplit('.').fold(String::new(), |mut path, label| {
+        path.push('/');
+        path.push_str(label);
+        path
+    })
+}
+
+/// Converts the labels captured by a catch-all back into host order, such that
+/// `/b/a` becomes `a.b`.
+fn path_to_host(path: &str) -> String {
+    path.trim_start_matches('/')
+        .rsplit('/')
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Converts a host pattern into a route path, see [`host_to_path`].
+fn pattern_to_path(pattern: &str) -> Result<String, &'static str> {
+    let labels = pattern.trim_end_matches('.').split('.').collect::<Vec<_>>();
+    let mut path = String::new();
+
+    for (i, label) in labels.iter().enumerate().rev() {
+        path.push('/');
+        if let Some(name) = label.strip_prefix('*') {
+            if i != 0 {
+                return Err("wildcards are only allowed in the leftmost label");
+            }
+
+            path.push('*');
+            path.push_str(if name.is_empty() { WILDCARD } else { name });
+        } else if label.starts_with(':') {
+            path.push_str(label);
+        } else if label.is_empty() || label.contains(&[':', '*', '/'][..]) {
+            return Err("invalid host label");
+        } else {
+            path.push_str(&label.to_ascii_lowercase());
+        }
+    }
+
+    Ok(path)
+}
+
+#[doc(hidden)]
+pub struct MakeHostRouterService(HostRouterService);
+
+impl<T> Service<T> for MakeHostRouterService {
+    type Response = HostRouterService;
+    type Error = hyper::Error;
+    type Future = future::Ready<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _: T) -> Self::Future {
+        let service = self.0.clone();
+        future::ok(service)
+    }
+}
+
+#[doc(hidden)]
+#[derive(Clone)]
+pub struct HostRouterService(Arc<HostRouter>);
+
+impl Service<Request<Body>> for HostRouterService {
+    type Response = Response<Body>;
+    type Error = BoxError;
+    type Future = ResponseFut;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        self.0.serve(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_to_path() {
+        let tests = vec![
+            ("example.com", "/com/example"),
+            ("Example.COM:8080", "/com/example"),
+            ("api.example.com.", "/com/example/api"),
+            ("127.0.0.1:3000", "/1/0/0/127"),
+            ("[::1]:3000", "/[::1]"),
+            ("[::1]", "/[::1]"),
+        ];
+
+        for (host, path) in tests {
+            assert_eq!(host_to_path(host), path);
+        }
+    }
+
+    #[test]
+    fn test_pattern_to_path() {
+        assert_eq!(
+            pattern_to_path(":tenant.Example.com").unwrap(),
+            "/com/example/:tenant"
+        );
+        assert_eq!(
+            pattern_to_path("*.example.com").unwrap(),
+            "/com/example/*__wildcard"
+        );
+        assert_eq!(strip_port(":tenant.example.com"), ":tenant.example.com");
+        assert_eq!(strip_port("example.com:80"), "example.com");
+        assert_eq!(
+            pattern_to_path("*sub.example.com").unwrap(),
+            "/com/example/*sub"
+        );
+        assert!(pattern_to_path("api.*.com").is_err());
+        assert!(pattern_to_path("api..com").is_err());
+        assert_eq!(path_to_host("/b/a"), "a.b");
+    }
+
+    #[tokio::test]
+    async fn dispatch_by_host() {
+        use crate::handler_fn;
+        use std::convert::Infallible;
+
+        let echo = |name: &'static str| {
+            handler_fn(move |req: Request<Body>| async move {
+                let params = req.extensions().get::<Params>().unwrap();
+                let tenant = params.get("tenant").unwrap_or("-");
+                let body = format!("{} {}", name, tenant);
+                Ok::<_, Infallible>(Response::new(Body::from(body)))
+            })
+        };
+
+        let router = HostRouter::default()
+            .host("api.example.com", Router::default().get("/", echo("api")))
+            .host(
+                ":tenant.example.com",
+                Router::default().get("/", echo("tenant")),
+            );
+
+        let tests = [
+            ("API.example.com:8080", "api -"),
+            ("acme.example.com", "tenant acme"),
+        ];
+
+        for (host, expected) in tests.iter() {
+            let req = Request::get("/")
+                .header(header::HOST, *host)
+                .body(Body::empty())
+                .unwrap();
+            let res = router.serve(req).await.unwrap();
+            let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            assert_eq!(body, *expected);
+        }
+
+        let req = Request::get("/")
+            .header(header::HOST, "example.org")
+            .body(Body::empty())
+            .unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.status(), hyper::StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,258 @@
+//! Applying `tower` middleware to the handlers of a [`Router`](crate::Router).
+use crate::router::{BoxError, StoredService};
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures_util::future::poll_fn;
+use hyper::service::Service;
+use hyper::{Body, Request, Response};
+use tower_layer::Layer;
+
+/// A handler registered on a [`Router`](crate::Router), as seen by the middleware
+/// applied with [`Router::layer`](crate::Router::layer) and
+/// [`Router::route_layer`](crate::Router::route_layer).
+#[derive(Clone)]
+pub struct Handler {
+    service: Box<dyn StoredService>,
+}
+
+impl Service<Request<Body>> for Handler {
+    type Response = Response<Body>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Response<Body>, BoxError>> + Send + Sync>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        self.service.call(req)
+    }
+}
+
+impl fmt::Debug for Handler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Handler").finish()
+    }
+}
+
+/// A type-erased `tower` layer.
+#[derive(Clone)]
+pub(crate) struct BoxLayer {
+    layer: Arc<LayerFn>,
+}
+
+type LayerFn = dyn Fn(Box<dyn StoredService>) -> Box<dyn StoredService> + Send + Sync;
+
+impl BoxLayer {
+    pub(crate) fn new<L>(layer: L) -> Self
+    where
+        L: Layer<Handler> + Send + Sync + 'static,
+        L::Service:
+            Service<Request<Body>, Response = Response<Body>> + Clone + Send + Sync + 'static,
+        <L::Service as Service<Request<Body>>>::Future: Send + Sync + 'static,
+        <L::Service as Service<Request<Body>>>::Error: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        Self {
+            layer: Arc::new(move |service| {
+                let service = layer.layer(Handler { service });
+                Box::new(Layered { service })
+            }),
+        }
+    }
+
+    /// Wraps the service with the layer.
+    pub(crate) fn apply(&self, service: Box<dyn StoredService>) -> Box<dyn StoredService> {
+        (self.layer)(service)
+    }
+}
+
+/// A service produced by a layer, stored in place of the handler it wraps.
+#[derive(Clone)]
+struct Layered<S> {
+    service: S,
+}
+
+impl<S> Service<Request<Body>> for Layered<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + Sync + 'static,
+    S::Future: Send + Sync + 'static,
+    S::Error: Into<Box<dyn StdError + Send + Sync>>,
+{
+    type Response = Response<Body>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Response<Body>, BoxError>> + Send + Sync>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        // middleware such as rate limiters rely on being polled for readiness
+        // before every call
+        let mut service = self.service.clone();
+        Box::pin(async move {
+            poll_fn(|cx| service.poll_ready(cx))
+                .await
+                .map_err(|err| BoxError::from_boxed(err.into()))?;
+            service
+                .call(req)
+                .await
+                .map_err(|err| BoxError::from_boxed(err.into()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{handler_fn, Router};
+
+    use std::convert::Infallible;
+
+    use hyper::header::HeaderValue;
+    use hyper::{Method, StatusCode};
+
+    /// A layer that adds a header to every response.
+    struct Tag(&'static str);
+
+    impl<S> Layer<S> for Tag {
+        type Service = Tagged<S>;
+
+        fn layer(&self, service: S) -> Self::Service {
+            Tagged(self.0, service)
+        }
+    }
+
+    #[derive(Clone)]
+    struct Tagged<S>(&'static str, S);
+
+    impl<S> Service<Request<Body>> for Tagged<S>
+    where
+        S: Service<Request<Body>, Response = Response<Body>, Error = BoxError>,
+        S::Future: Send + Sync + 'static,
+    {
+        type Response = Response<Body>;
+        type Error = BoxError;
+        type Future = Pin<Box<dyn Future<Output = Result<Response<Body>, BoxError>> + Send + Sync>>;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.1.poll_ready(cx)
+        }
+
+        fn call(&mut self, req: Request<Body>) -> Self::Future {
+            let tag = self.0;
+            let fut = self.1.call(req);
+            Box::pin(async move {
+                let mut res = fut.await?;
+                res.headers_mut()
+                    .append("x-layer", HeaderValue::from_static(tag));
+                Ok(res)
+            })
+        }
+    }
+
+    fn tags(res: &Response<Body>) -> Vec<&str> {
+        res.headers()
+            .get_all("x-layer")
+            .iter()
+            .map(|value| value.to_str().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn layers() {
+        let ok = handler_fn(|_| async { Ok::<_, Infallible>(Response::new(Body::empty())) });
+        let not_found = handler_fn(|_| async {
+            Ok::<_, Infallible>(Response::builder().status(404).body(Body::empty()).unwrap())
+        });
+
+        let router = Router::default()
+            .get("/before", ok.clone())
+            .layer(Tag("outer"))
+            .route_layer(Tag("route"))
+            .get("/after", ok.clone())
+            .not_found(not_found);
+
+        // routes are wrapped regardless of when they were registered, with later
+        // layers wrapping earlier ones
+        for path in ["/before", "/after"].iter() {
+            let req = Request::get(*path).body(Body::empty()).unwrap();
+            let res = router.serve(req).await.unwrap();
+            assert_eq!(tags(&res), ["outer", "route"], "{}", path);
+        }
+
+        // fallback handlers are only wrapped by `layer`
+        let req = Request::get("/missing").body(Body::empty()).unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(tags(&res), ["outer"]);
+
+        // routes added while serving are wrapped as well
+        let (mut make_service, handle) = router.into_service_with_handle();
+        handle.add("/live", Method::GET, ok).unwrap();
+
+        let mut service = make_service.call(()).await.unwrap();
+        let req = Request::get("/live").body(Body::empty()).unwrap();
+        let res = service.call(req).await.unwrap();
+        assert_eq!(tags(&res), ["outer", "route"]);
+    }
+
+    #[tokio::test]
+    async fn route_and_group_layers() {
+        let ok = handler_fn(|_| async { Ok::<_, Infallible>(Response::new(Body::empty())) });
+
+        let router = Router::default()
+            .route_layer(Tag("router"))
+            .get("/", ok.clone())
+            .handle_layered("/upload", Method::POST, ok.clone(), Tag("upload"))
+            .group("/admin", |g| {
+                g.layer(Tag("admin"))
+                    .get("/users", ok.clone())
+                    .group("/audit", |g| g.layer(Tag("audit")).get("/log", ok.clone()))
+            });
+
+        for (method, path, expected) in [
+            (Method::GET, "/", &["router"][..]),
+            (Method::POST, "/upload", &["upload", "router"][..]),
+            (Method::GET, "/admin/users", &["admin", "router"][..]),
+            (
+                Method::GET,
+                "/admin/audit/log",
+                &["audit", "admin", "router"][..],
+            ),
+        ]
+        .iter()
+        {
+            let req = Request::builder()
+                .method(method)
+                .uri(*path)
+                .body(Body::empty())
+                .unwrap();
+            let res = router.serve(req).await.unwrap();
+            assert_eq!(tags(&res), *expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn layers_keep_nested_names() {
+        let ok = handler_fn(|_| async { Ok::<_, Infallible>(Response::new(Body::empty())) });
+        let users = Router::default().handle_named("user", "/users/:id", Method::GET, ok);
+
+        let router = Router::default()
+            .nest("/api", users)
+            .layer(Tag("outer"))
+            .route_layer(Tag("route"));
+
+        assert_eq!(
+            router.url_for("user", &[("id", "1")]).unwrap(),
+            "/api/users/1"
+        );
+        assert_eq!(router.routes().count(), 1);
+    }
+}
@@ -23,7 +23,7 @@
 //!
 //! **High Performance:** HttpRouter relies on a tree structure which makes heavy use of *common prefixes*, it is basically a [radix tree](https://en.wikipedia.org/wiki/Radix_tree). This makes lookups extremely fast. Internally, it uses the [matchit](https://github.com/ibraheemdev/matchit) package.
 //!
-//! Of course you can also set **custom [`NotFound`](https://docs.rs/httprouter/newest/httprouter/router/struct.Router.html#method.not_found) and  [`MethodNotAllowed`](https://docs.rs/httprouter/newest/httprouter/router/struct.Router.html#method.method_not_allowed) handlers** , [**serve static files**](https://docs.rs/httprouter/newest/httprouter/router/struct.Router.html#method.serve_files), and [**automatically respond to OPTIONS requests**](https://docs.rs/httprouter/newest/httprouter/router/struct.Router.html#method.global_options)
+//! Of course you can also set **custom [`NotFound`](https://docs.rs/httprouter/newest/httprouter/router/struct.Router.html#method.not_found) and  [`MethodNotAllowed`](https://docs.rs/httprouter/newest/httprouter/router/struct.Router.html#method.method_not_allowed) handlers** , [**serve static files**](https://docs.rs/httprouter/newest/httprouter/router/struct.Router.html#method.serve_files), [**automatically respond to OPTIONS requests**](https://docs.rs/httprouter/newest/httprouter/router/struct.Router.html#method.global_options), and [**answer HEAD requests with GET routes**](https://docs.rs/httprouter/newest/httprouter/router/struct.Router.html#method.auto_head)
 //!
 //! ## Usage
 //!
@@ -56,7 +56,7 @@
 //!
 //! ### Named parameters
 //!
-//! As you can see, `:user` is a *named parameter*. The values are accessible via `req.extensions().get::<Params>()`.
+//! As you can see, `:user` is a *named parameter*. The values are accessible via `req.extensions().get::<Params>()`. Values are percent-decoded, so `/hello/John%20Doe` yields `John Doe`, while [`Params::get_raw`](https://docs.rs/httprouter/newest/httprouter/router/struct.Params.html#method.get_raw) returns the value as it appears in the path. Routes are matched against the decoded path, unless [`Router::use_raw_path`](https://docs.rs/httprouter/newest/httprouter/router/struct.Router.html#method.use_raw_path) is enabled.
 //!
 //! Named parameters only match a single path segment:
 //!
@@ -83,6 +83,67 @@
 //!  /src/subdir/somefile.go   match
 //! ```
 //!
+//! ### Parameter constraints
+//!
+//! A parameter can be followed by a constraint in angle brackets, either the name of a primitive type or a regular expression that must match the whole value. Requests whose parameters don't satisfy the constraints are treated as if the route didn't match, so they can still be answered by another route, a redirect or a `405 Method Not Allowed` response, and are otherwise passed to the `NotFound` handler:
+//!
+//! ```text
+//! Pattern: /orders/:id<u64>
+//!
+//!  /orders/42                match
+//!  /orders/abc               no match
+//!
+//! Pattern: /files/:name<[a-z0-9_-]+>
+//!
+//!  /files/read_me            match
+//!  /files/README             no match
+//! ```
+//!
+//! ### Route groups
+//!
+//! Routes that share a common prefix can be registered together with [`Router::group`](https://docs.rs/httprouter/newest/httprouter/router/struct.Router.html#method.group):
+//!
+//! ```rust
+//! use httprouter::{Router, handler_fn};
+//! use hyper::{Request, Response, Body, Error};
+//!
+//! async fn user(_: Request<Body>) -> Result<Response<Body>, Error> {
+//!     Ok(Response::new("User".into()))
+//! }
+//!
+//! fn main() {
+//!     let router = Router::default().group("/api/v2", |g| {
+//!         g.get("/users/:id", handler_fn(user))
+//!             .put("/users/:id", handler_fn(user))
+//!     });
+//! }
+//! ```
+//!
+//! ### Middleware
+//!
+//! Any [`tower::Layer`](https://docs.rs/tower/latest/tower/trait.Layer.html), such as a timeout or tracing middleware, can wrap the routes of a router with [`Router::layer`](https://docs.rs/httprouter/newest/httprouter/router/struct.Router.html#method.layer), which also wraps the `NotFound` and `MethodNotAllowed` handlers, or with [`Router::route_layer`](https://docs.rs/httprouter/newest/httprouter/router/struct.Router.html#method.route_layer), which only wraps matched routes:
+//!
+//! ```rust
+//! use httprouter::{Router, handler_fn};
+//! use hyper::{Request, Response, Body, Error};
+//! use std::time::Duration;
+//! use tower::timeout::TimeoutLayer;
+//!
+//! async fn index(_: Request<Body>) -> Result<Response<Body>, Error> {
+//!     Ok(Response::new("Hello, World!".into()))
+//! }
+//!
+//! fn main() {
+//!     let router = Router::default()
+//!         .get("/", handler_fn(index))
+//!         .layer(TimeoutLayer::new(Duration::from_secs(30)));
+//! }
+//! ```
+//!
+//! Middleware that only applies to a single route or to a group of routes can be attached when registering them, with [`Router::handle_layered`](https://docs.rs/httprouter/newest/httprouter/router/struct.Router.html#method.handle_layered) and [`Group::layer`](https://docs.rs/httprouter/newest/httprouter/struct.Group.html#method.layer).
+//!
+//! Panics and errors in handlers can be turned into responses, such as a `500 Internal Server Error`, with [`Router::panic_handler`](https://docs.rs/httprouter/newest/httprouter/router/struct.Router.html#method.panic_handler) and [`Router::error_handler`](https://docs.rs/httprouter/newest/httprouter/router/struct.Router.html#method.error_handler).
+//!
 //! ## Automatic OPTIONS responses and CORS
 //!
 //! One might wish to modify automatic responses to OPTIONS requests, e.g. to support [CORS preflight requests](https://developer.mozilla.org/en-US/docs/Glossary/preflight_request) or to set other headers. This can be achieved using the [`Router::global_options`](https://docs.rs/httprouter/newest/httprouter/router/struct.Router.html#method.global_options) handler:
@@ -110,55 +171,28 @@
 //! Here is a quick example: Does your server serve multiple domains / hosts? You want to use sub-domains? Define a router per host!
 //!
 //! ```rust,no_run
-//! use httprouter::{Router, BoxError};
-//! use hyper::service::{make_service_fn, service_fn};
-//! # use hyper::{Body, Request, Response};
-//! # use std::collections::HashMap;
-//! # use std::convert::Infallible;
-//! # use std::sync::Arc;
-//!
-//! #[derive(Default)]
-//! pub struct HostSwitch(HashMap<String, Router>);
-//!
-//! impl HostSwitch {
-//!     async fn serve(&self, req: Request<Body>) -> Result<Response<Body>, BoxError> {
-//!         # let not_found = Response::builder()
-//!         #     .status(404)
-//!         #     .body(Body::empty())
-//!         #     .unwrap();
-//!         match req.headers().get("host") {
-//!             Some(host) => match self.0.get(host.to_str().unwrap()) {
-//!                 Some(router) => router.serve(req).await,
-//!                 None => Ok(not_found),
-//!             },
-//!             None => Ok(not_found),
-//!         }
-//!     }
+//! use httprouter::{HostRouter, Params, Router, handler_fn};
+//! use hyper::{Request, Response, Body, Error};
+//!
+//! async fn tenant(req: Request<Body>) -> Result<Response<Body>, Error> {
+//!     let params = req.extensions().get::<Params>().unwrap();
+//!     Ok(Response::new(format!("Welcome, {}", params.get("tenant").unwrap()).into()))
 //! }
 //!
 //! #[tokio::main]
 //! async fn main() {
-//!     let mut host_switch = HostSwitch::default();
-//!     host_switch.0.insert("example.com:12345".into(), Router::default());
-//!
-//!     let host_switch = Arc::new(host_switch);
-//!     
-//!     let make_svc = make_service_fn(move |_| {
-//!         let host_switch = host_switch.clone();
-//!         async move {
-//!             Ok::<_, Infallible>(service_fn(move |req| {
-//!                 let host_switch = host_switch.clone();
-//!                 async move { host_switch.serve(req).await }
-//!             }))
-//!         }
-//!     });
+//!     let router = HostRouter::default()
+//!         .host("example.com", Router::default())
+//!         .host(":tenant.example.com", Router::default().get("/", handler_fn(tenant)));
 //!
 //!     hyper::Server::bind(&([127, 0, 0, 1], 3000).into())
-//!         .serve(make_svc)
+//!         .serve(router.into_service())
 //!         .await;
 //! }
 //! ```
 //!
+//! Host patterns support named parameters such as `:tenant` and wildcard subdomains such as `*.example.com`. Matching ignores the port and the case of the host, and requests for unknown hosts are passed to the [`HostRouter::fallback`](https://docs.rs/httprouter/newest/httprouter/struct.HostRouter.html#method.fallback) handler.
+//!
 //! ### Not Found Handler
 //!
 //! **NOTE: It might be required to set [`Router::method_not_allowed`](https://docs.rs/httprouter/newest/httprouter/router/struct.Router.html#structfield.method_not_allowed) to `None` to avoid problems.**
@@ -173,10 +207,10 @@
 //!
 //! async fn not_found(req: Request<Body>) -> Result<Response<Body>, Error> {
 //!     let res = Response::builder()
-//! 	    .status(404)
-//! 	    .body(Body::empty())
-//! 	    .unwrap();
-//! 	Ok(res)
+//!         .status(404)
+//!         .body(Body::empty())
+//!         .unwrap();
+//!     Ok(res)
 //! }
 //!
 //! fn main() {
@@ -184,24 +218,67 @@
 //! }
 //! ```
 //!
+//! Without a `not_found` handler, unmatched requests are answered with an empty `404 Not Found`. The bodies of the responses that the router generates itself, such as `404 Not Found`, `405 Method Not Allowed` and redirects, can be customized with [`Router::responder`](https://docs.rs/httprouter/newest/httprouter/router/struct.Router.html#method.responder), and [`Router::problem_details`](https://docs.rs/httprouter/newest/httprouter/router/struct.Router.html#method.problem_details) describes them with an [RFC 7807](https://www.rfc-editor.org/rfc/rfc7807) `application/problem+json` object, or with an HTML page for browsers.
+//!
 //! ### Static files
 //!
 //! You can use the router to serve pages from a static file directory:
 //!
-//! ```rust
-//! // TODO
+//! ```rust,no_run
+//! use httprouter::Router;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     // `GET /static/css/app.css` serves the file `./public/css/app.css`
+//!     let router = Router::default().serve_files("/static/*filepath", "./public");
+//!
+//!     hyper::Server::bind(&([127, 0, 0, 1], 3000).into())
+//!         .serve(router.into_service())
+//!         .await;
+//! }
 //! ```
+//!
+//! The path must end with a `*filepath` catch-all parameter. Range and conditional requests are supported, and files outside of the root directory are never served. Assets can also be embedded in the binary and served from memory with [`MemorySource`](https://docs.rs/httprouter/newest/httprouter/struct.MemorySource.html). With the `tar` and `zip` features enabled, files can also be served straight out of an archive with `TarSource` and `ZipSource`. [`StaticFiles::fingerprint`](https://docs.rs/httprouter/newest/httprouter/struct.StaticFiles.html#method.fingerprint) serves assets under cache-busting URLs such as `/static/app.3f9a2c1b.js`. Single-page applications can use [`Router::spa_fallback`](https://docs.rs/httprouter/newest/httprouter/router/struct.Router.html#method.spa_fallback) to answer page requests for their client-side routes with `index.html`.
 
 #![forbid(unsafe_code)]
 
+mod constraint;
+mod de;
+mod fs;
+mod group;
+mod handle;
+mod host;
+mod layer;
+mod nest;
 pub(crate) mod path;
+mod recover;
+mod redirect;
+mod respond;
 
 #[doc(hidden)]
 pub mod router;
 
+pub use de::ParamsError;
+#[cfg(feature = "tar")]
+pub use fs::TarSource;
+#[cfg(feature = "zip")]
+pub use fs::ZipSource;
+pub use fs::{
+    AssetManifest, DirEntry, DiskSource, FileSource, MemorySource, SourceEntry, SourceFile,
+    StaticFiles,
+};
+pub use group::Group;
+pub use handle::RouterHandle;
+pub use host::HostRouter;
+pub use layer::Handler;
+pub use recover::RequestParts;
+pub use redirect::RedirectPolicy;
+pub use respond::BuiltinResponse;
+
 #[doc(inline)]
 pub use router::{
-    handler_fn, BoxError, HandlerError, HandlerFuture, HandlerService, Params, Router,
+    handler_fn, BoxError, HandlerError, HandlerFuture, HandlerService, Params, Route, RouteError,
+    Router, UrlError,
 };
 
 // test the code examples in README.md
@@ -0,0 +1,75 @@
+use crate::router::Params;
+
+use std::task::{Context, Poll};
+
+use hyper::service::Service;
+use hyper::{Body, Request, Uri};
+
+/// The name of the catch-all parameter that captures the remainder of a nested path.
+pub(crate) const NESTED_PATH: &str = "__nested_path";
+
+/// The portion of the request path that was stripped by the parent router(s).
+///
+/// Nested routers prepend it to the `Location` of redirects so that clients are sent
+/// back through the parent.
+#[derive(Clone, Debug)]
+pub(crate) struct NestedPrefix(pub(crate) String);
+
+/// A service mounted under a path prefix with [`Router::nest`](crate::Router::nest).
+#[derive(Clone)]
+pub(crate) struct Nested<S> {
+    service: S,
+}
+
+impl<S> Nested<S> {
+    pub(crate) fn new(service: S) -> Self {
+        Self { service }
+    }
+}
+
+impl<S> Service<Request<Body>> for Nested<S>
+where
+    S: Service<Request<Body>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        let rest = req
+            .extensions_mut()
+            .get_mut::<Params>()
+            .and_then(|params| params.remove_raw(NESTED_PATH))
+            .unwrap_or_default();
+
+        let rest = if rest.starts_with('/') {
+            rest
+        } else {
+            ["/", &rest].concat()
+        };
+
+        let path = req.uri().path();
+        let stripped = &path[..path.len().saturating_sub(rest.len())];
+        let prefix = match req.extensions().get::<NestedPrefix>() {
+            Some(NestedPrefix(parent)) => [parent.as_str(), stripped].concat(),
+            None => stripped.to_owned(),
+        };
+
+        let path_and_query = match req.uri().query() {
+            Some(query) => [rest.as_str(), "?", query].concat(),
+            None => rest,
+        };
+
+        let mut parts = req.uri().clone().into_parts();
+        // the remainder was matched from the original path, so it is always valid
+        parts.path_and_query = Some(path_and_query.parse().unwrap());
+        *req.uri_mut() = Uri::from_parts(parts).unwrap();
+        req.extensions_mut().insert(NestedPrefix(prefix));
+
+        self.service.call(req)
+    }
+}
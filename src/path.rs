@@ -1,5 +1,135 @@
 //! Utility methods for URL paths
 
+use std::borrow::Cow;
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+/// Characters that must be percent-encoded in a path segment: everything except
+/// the unreserved characters.
+const SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Characters that must be percent-encoded in a path spanning multiple segments.
+const PATH: &AsciiSet = &SEGMENT.remove(b'/');
+
+/// Percent-encodes a single path segment, including any `/`.
+pub fn encode_segment(s: &str) -> String {
+    utf8_percent_encode(s, SEGMENT).to_string()
+}
+
+/// Percent-encodes a path, preserving `/` separators.
+pub fn encode_path(s: &str) -> String {
+    utf8_percent_encode(s, PATH).to_string()
+}
+
+/// Percent-decodes a path parameter, returning it unchanged if it does not decode to
+/// UTF-8.
+pub fn decode(s: &str) -> Cow<'_, str> {
+    percent_decode_str(s)
+        .decode_utf8()
+        .unwrap_or(Cow::Borrowed(s))
+}
+
+/// A percent-decoded path, with the position of each of its bytes in the encoded path.
+pub struct DecodedPath {
+    path: String,
+    // the offset in the encoded path of each byte, and of the end of the path
+    offsets: Vec<usize>,
+}
+
+impl DecodedPath {
+    /// Decodes the path, or returns `None` if it contains no percent-encodings or does
+    /// not decode to UTF-8.
+    pub fn new(encoded: &str) -> Option<Self> {
+        if !encoded.contains('%') {
+            return None;
+        }
+
+        let bytes = encoded.as_bytes();
+        let mut path = Vec::with_capacity(bytes.len());
+        let mut offsets = Vec::with_capacity(bytes.len() + 1);
+
+        let mut i = 0;
+        while i < bytes.len() {
+            offsets.push(i);
+            match percent_encoded(bytes, i) {
+                Some(byte) => {
+                    path.push(byte);
+                    i += 3;
+                }
+                None => {
+                    path.push(bytes[i]);
+                    i += 1;
+                }
+            }
+        }
+        offsets.push(bytes.len());
+
+        let path = String::from_utf8(path).ok()?;
+        Some(Self { path, offsets })
+    }
+
+    /// The decoded path.
+    pub fn as_str(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the slice of the encoded path that the given slice of the decoded path
+    /// was decoded from.
+    pub fn encoded<'a>(&self, encoded: &'a str, decoded: &str) -> Option<&'a str> {
+        let start = (decoded.as_ptr() as usize).checked_sub(self.path.as_ptr() as usize)?;
+        let end = start + decoded.len();
+        if end > self.path.len() {
+            return None;
+        }
+
+        encoded.get(self.offsets[start]..self.offsets[end])
+    }
+}
+
+/// Decodes the percent-encoded unreserved characters in a path, such as `%2E` or
+/// `%7E`, which are equivalent to the characters themselves, and uppercases the hex
+/// digits of the other percent-encodings. This allows `clean` to remove encoded dot
+/// segments from a path that is matched without being decoded, while preserving
+/// encoded separators such as `%2F`.
+pub fn normalize_encoding(p: &str) -> String {
+    let bytes = p.as_bytes();
+    let mut normalized = String::with_capacity(p.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        match percent_encoded(bytes, i) {
+            Some(byte) if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) => {
+                normalized.push(byte as char);
+                i += 3;
+            }
+            Some(_) => {
+                normalized.push_str(&p[i..i + 3].to_ascii_uppercase());
+                i += 3;
+            }
+            None => {
+                let len = p[i..].chars().next().map_or(1, char::len_utf8);
+                normalized.push_str(&p[i..i + len]);
+                i += len;
+            }
+        }
+    }
+
+    normalized
+}
+
+/// Returns the byte encoded at the given position, if it starts a percent-encoding.
+fn percent_encoded(bytes: &[u8], i: usize) -> Option<u8> {
+    let hex = |byte: u8| (byte as char).to_digit(16).map(|digit| digit as u8);
+    match bytes.get(i..i + 3)? {
+        [b'%', high, low] => Some(hex(*high)? << 4 | hex(*low)?),
+        _ => None,
+    }
+}
+
 /// The following rules are applied iteratively until no further processing can
 /// be done:
 /// 1. Replace multiple slashes with a single slash.
@@ -12,7 +142,7 @@
 /// If the result of this process is an empty string, "/" is returned
 pub fn clean(p: &str) -> String {
     // Turn empty string into "/"
-    if p == "" {
+    if p.is_empty() {
         return "/".to_string();
     }
 
@@ -96,6 +226,24 @@ pub fn clean(p: &str) -> String {
     String::from_utf8(buf[..w].to_vec()).unwrap()
 }
 
+/// Joins a route prefix and a path with exactly one slash between them.
+///
+/// Trailing slashes on the path are preserved, so `("/api", "/")` yields `/api/`,
+/// while an empty path refers to the prefix itself.
+pub fn join(prefix: &str, path: &str) -> String {
+    if path.is_empty() {
+        return if prefix.is_empty() {
+            "/".to_string()
+        } else {
+            prefix.to_string()
+        };
+    }
+
+    let prefix = prefix.trim_end_matches('/');
+    let path = path.trim_start_matches('/');
+    [prefix, "/", path].concat()
+}
+
 #[inline]
 fn buf_app(buf: &mut Vec<u8>, s: &[u8], w: usize, c: u8) {
     if buf.is_empty() {
@@ -178,6 +326,52 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_decoded_path() {
+        let encoded = "/users/John%20Doe/files/a%2Fb";
+        let decoded = DecodedPath::new(encoded).unwrap();
+        assert_eq!(decoded.as_str(), "/users/John Doe/files/a/b");
+
+        let name = &decoded.as_str()[7..15];
+        assert_eq!(name, "John Doe");
+        assert_eq!(decoded.encoded(encoded, name), Some("John%20Doe"));
+        let rest = &decoded.as_str()[22..];
+        assert_eq!(decoded.encoded(encoded, rest), Some("a%2Fb"));
+
+        assert!(DecodedPath::new("/users/john").is_none());
+        assert!(DecodedPath::new("/%FF").is_none());
+        assert_eq!(DecodedPath::new("/100%").unwrap().as_str(), "/100%");
+    }
+
+    #[test]
+    fn test_normalize_encoding() {
+        assert_eq!(normalize_encoding("/a/%2e%2E/b"), "/a/../b");
+        assert_eq!(normalize_encoding("/a%2fb/%7euser"), "/a%2Fb/~user");
+        assert_eq!(normalize_encoding("/50%/%zz"), "/50%/%zz");
+        assert_eq!(clean(&normalize_encoding("/a/b/%2E%2E/c")), "/a/c");
+    }
+
+    #[test]
+    fn test_path_join() {
+        let tests = vec![
+            // (prefix, path, result)
+            ("/api/v2", "/users/:id", "/api/v2/users/:id"),
+            ("/api/v2/", "/users/:id", "/api/v2/users/:id"),
+            ("/api/v2", "users/:id", "/api/v2/users/:id"),
+            ("/api", "/users/", "/api/users/"),
+            ("/api", "/", "/api/"),
+            ("/api", "", "/api"),
+            ("/api/", "", "/api/"),
+            ("/", "/users", "/users"),
+            ("", "/users", "/users"),
+            ("", "", "/"),
+        ];
+
+        for (prefix, path, result) in tests {
+            assert_eq!(join(prefix, path), result);
+        }
+    }
+
     #[test]
     fn test_path_clean_long() {
         let mut test_paths: Vec<(String, String)> = Vec::new();
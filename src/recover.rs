@@ -0,0 +1,259 @@
+//! Recovering from handlers that panic or fail.
+use crate::router::{BoxError, StoredService};
+
+use std::any::Any;
+use std::fmt;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use hyper::service::Service;
+use hyper::{Body, HeaderMap, Method, Request, Response, Uri, Version};
+
+/// The metadata of a request whose handler failed, see
+/// [`Router::panic_handler`](crate::Router::panic_handler) and
+/// [`Router::error_handler`](crate::Router::error_handler).
+#[derive(Clone, Debug)]
+pub struct RequestParts {
+    method: Method,
+    uri: Uri,
+    version: Version,
+    headers: HeaderMap,
+}
+
+impl RequestParts {
+    pub(crate) fn new(req: &Request<Body>) -> Self {
+        Self {
+            method: req.method().clone(),
+            uri: req.uri().clone(),
+            version: req.version(),
+            headers: req.headers().clone(),
+        }
+    }
+
+    /// The method of the request.
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// The URI of the request.
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
+    /// The HTTP version of the request.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// The headers of the request.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+}
+
+/// Builds a response from the payload of a panic.
+pub(crate) type PanicHandler =
+    Arc<dyn Fn(Box<dyn Any + Send>, RequestParts) -> Response<Body> + Send + Sync>;
+
+/// Builds a response from the error returned by a handler.
+pub(crate) type ErrorHandler = Arc<dyn Fn(BoxError, RequestParts) -> Response<Body> + Send + Sync>;
+
+/// A future that calls a handler, and answers with the panic handler if calling or
+/// polling it panics.
+pub(crate) struct CatchPanic {
+    state: State,
+    handler: PanicHandler,
+}
+
+enum State {
+    Pending {
+        future: Pin<Box<dyn Future<Output = Result<Response<Body>, BoxError>> + Send + Sync>>,
+        parts: RequestParts,
+    },
+    // the response of the panic handler to a panic in the call to the handler
+    Panicked(Response<Body>),
+    Done,
+}
+
+impl CatchPanic {
+    pub(crate) fn new(
+        mut service: Box<dyn StoredService>,
+        req: Request<Body>,
+        parts: RequestParts,
+        handler: PanicHandler,
+    ) -> Self {
+        let state = match panic::catch_unwind(AssertUnwindSafe(|| service.call(req))) {
+            Ok(future) => State::Pending { future, parts },
+            Err(payload) => State::Panicked(handler(payload, parts)),
+        };
+
+        Self { state, handler }
+    }
+}
+
+impl Future for CatchPanic {
+    type Output = Result<Response<Body>, BoxError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let payload = match this.state {
+            State::Pending { ref mut future, .. } => {
+                match panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(cx))) {
+                    Ok(Poll::Pending) => return Poll::Pending,
+                    Ok(Poll::Ready(res)) => {
+                        this.state = State::Done;
+                        return Poll::Ready(res);
+                    }
+                    Err(payload) => Some(payload),
+                }
+            }
+            _ => None,
+        };
+
+        match (std::mem::replace(&mut this.state, State::Done), payload) {
+            (State::Pending { parts, .. }, Some(payload)) => {
+                Poll::Ready(Ok((this.handler)(payload, parts)))
+            }
+            (State::Panicked(res), _) => Poll::Ready(Ok(res)),
+            _ => panic!("polled after completion"),
+        }
+    }
+}
+
+impl fmt::Debug for CatchPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CatchPanic").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{handler_fn, Params, ParamsError, Router};
+
+    use std::convert::Infallible;
+    use std::{fmt, io};
+
+    use hyper::{Body, Request, Response, StatusCode};
+
+    #[tokio::test]
+    async fn catch_panics() {
+        let router = Router::default()
+            .get(
+                "/call",
+                handler_fn(
+                    |_| -> futures_util::future::Ready<Result<Response<Body>, Infallible>> {
+                        panic!("panicked in call")
+                    },
+                ),
+            )
+            .get(
+                "/poll",
+                handler_fn(|_| async {
+                    tokio::task::yield_now().await;
+                    if true {
+                        panic!("panicked in {}", "poll");
+                    }
+                    Ok::<_, Infallible>(Response::new(Body::empty()))
+                }),
+            )
+            .panic_handler(|payload, parts| {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+
+                Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(format!("{} {}: {}", parts.method(), parts.uri(), message).into())
+                    .unwrap()
+            });
+
+        for (path, message) in [
+            ("/call", "GET /call: panicked in call"),
+            ("/poll", "GET /poll: panicked in poll"),
+        ]
+        .iter()
+        {
+            let req = Request::get(*path).body(Body::empty()).unwrap();
+            let res = router.serve(req).await.unwrap();
+            assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+            let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            assert_eq!(body, *message);
+        }
+    }
+
+    #[derive(Debug)]
+    struct Teapot;
+
+    impl fmt::Display for Teapot {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("short and stout")
+        }
+    }
+
+    impl std::error::Error for Teapot {}
+
+    #[tokio::test]
+    async fn handle_errors() {
+        let router = Router::default()
+            .get(
+                "/teapot",
+                handler_fn(|_| async { Err::<Response<Body>, _>(Teapot) }),
+            )
+            .get(
+                "/io",
+                handler_fn(|_| async {
+                    Err::<Response<Body>, _>(io::Error::new(io::ErrorKind::NotFound, "gone"))
+                }),
+            )
+            .get(
+                "/params/:id",
+                handler_fn(|req: Request<Body>| async move {
+                    req.extensions()
+                        .get::<Params>()
+                        .unwrap()
+                        .parse::<u32>("id")?;
+                    Ok::<_, ParamsError>(Response::new(Body::empty()))
+                }),
+            )
+            .panic_handler(|_, _| unreachable!())
+            .error_handler(|err, parts| {
+                let status = if err.is::<Teapot>() {
+                    StatusCode::IM_A_TEAPOT
+                } else {
+                    StatusCode::INTERNAL_SERVER_ERROR
+                };
+
+                Response::builder()
+                    .status(status)
+                    .body(format!("{} {}: {}", parts.method(), parts.uri(), err).into())
+                    .unwrap()
+            });
+
+        for (path, status, message) in [
+            (
+                "/teapot",
+                StatusCode::IM_A_TEAPOT,
+                "GET /teapot: short and stout",
+            ),
+            ("/io", StatusCode::INTERNAL_SERVER_ERROR, "GET /io: gone"),
+        ]
+        .iter()
+        {
+            let req = Request::get(*path).body(Body::empty()).unwrap();
+            let res = router.serve(req).await.unwrap();
+            assert_eq!(res.status(), *status);
+            let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            assert_eq!(body, *message);
+        }
+
+        // parameter errors are still bad requests
+        let req = Request::get("/params/abc").body(Body::empty()).unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,290 @@
+//! How the router redirects requests to the canonical path of a route.
+use crate::nest::NestedPrefix;
+
+use std::sync::Arc;
+
+use hyper::{Body, Method, Request, StatusCode};
+
+/// Configures the redirects of a [`Router`](crate::Router) to the path of a route
+/// with or without a trailing slash, and to the cleaned and case-corrected path of
+/// a route, see [`Router::redirect_policy`](crate::Router::redirect_policy).
+///
+/// By default, `GET` and `HEAD` requests are redirected with `301 Moved Permanently`,
+/// other requests with `308 Permanent Redirect`, and the query string of the
+/// request is preserved.
+/// ```rust
+/// use httprouter::{RedirectPolicy, Router};
+/// use hyper::StatusCode;
+///
+/// let policy = RedirectPolicy::default()
+///     .trailing_slash_status(StatusCode::FOUND, StatusCode::TEMPORARY_REDIRECT)
+///     .base_url("https://example.com")
+///     .redirect_non_idempotent(false);
+///
+/// let router = Router::default().redirect_policy(policy);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RedirectPolicy {
+    // the statuses for `GET` and `HEAD` requests, and for other requests
+    trailing_slash: (StatusCode, StatusCode),
+    fixed_path: (StatusCode, StatusCode),
+    preserve_query: bool,
+    base_url: Option<String>,
+    forwarded_prefix: bool,
+    redirect_non_idempotent: bool,
+}
+
+/// The redirects of the router that matched a request, read at request time by
+/// [`StaticFiles`](crate::StaticFiles) to redirect directories like routes.
+#[derive(Clone, Debug)]
+pub(crate) struct RouterRedirects {
+    /// Whether [`Router::redirect_trailing_slash`](crate::Router::redirect_trailing_slash)
+    /// is enabled.
+    pub(crate) trailing_slash: bool,
+    pub(crate) policy: Arc<RedirectPolicy>,
+}
+
+impl Default for RouterRedirects {
+    fn default() -> Self {
+        Self {
+            trailing_slash: true,
+            policy: Arc::new(RedirectPolicy::default()),
+        }
+    }
+}
+
+/// The reason for a redirect.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Redirect {
+    /// To the path with or without a trailing slash.
+    TrailingSlash,
+    /// To the cleaned and case-corrected path.
+    FixedPath,
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        Self {
+            trailing_slash: (
+                StatusCode::MOVED_PERMANENTLY,
+                StatusCode::PERMANENT_REDIRECT,
+            ),
+            fixed_path: (
+                StatusCode::MOVED_PERMANENTLY,
+                StatusCode::PERMANENT_REDIRECT,
+            ),
+            preserve_query: true,
+            base_url: None,
+            forwarded_prefix: false,
+            redirect_non_idempotent: true,
+        }
+    }
+}
+
+impl RedirectPolicy {
+    /// The statuses of redirects to the path with or without a trailing slash, for
+    /// `GET` and `HEAD` requests and for other requests. Use
+    /// `307 Temporary Redirect` or `308 Permanent Redirect` for the latter, to have
+    /// clients repeat the request with the same method and body.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a status is not one of `301`, `302`, `307` or `308`.
+    pub fn trailing_slash_status(mut self, get: StatusCode, other: StatusCode) -> Self {
+        self.trailing_slash = (redirect_status(get), redirect_status(other));
+        self
+    }
+
+    /// The statuses of redirects to the cleaned and case-corrected path, for `GET`
+    /// and `HEAD` requests and for other requests.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a status is not one of `301`, `302`, `307` or `308`.
+    pub fn fixed_path_status(mut self, get: StatusCode, other: StatusCode) -> Self {
+        self.fixed_path = (redirect_status(get), redirect_status(other));
+        self
+    }
+
+    /// Whether the query string of the request is appended to the `Location` of the
+    /// redirect. Enabled by default.
+    pub fn preserve_query(mut self, enabled: bool) -> Self {
+        self.preserve_query = enabled;
+        self
+    }
+
+    /// Redirect to absolute URLs, by prepending the given scheme, authority and
+    /// optional path prefix to the path, such as `https://example.com/app`.
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        let mut url = url.into();
+        if url.ends_with('/') {
+            url.pop();
+        }
+
+        self.base_url = Some(url);
+        self
+    }
+
+    /// Whether the path prefix in the `X-Forwarded-Prefix` header of the request,
+    /// set by a reverse proxy that strips it before forwarding the request, is
+    /// prepended to the path. Only enable this behind a proxy that sets the header.
+    pub fn forwarded_prefix(mut self, enabled: bool) -> Self {
+        self.forwarded_prefix = enabled;
+        self
+    }
+
+    /// Whether requests with a non-idempotent method, such as `POST` or `PATCH`, are
+    /// redirected. If disabled, they are answered as if no route matched the path.
+    /// Enabled by default.
+    pub fn redirect_non_idempotent(mut self, enabled: bool) -> Self {
+        self.redirect_non_idempotent = enabled;
+        self
+    }
+
+    /// Returns the status of the redirect of a request with the given method, or
+    /// `None` if the request should not be redirected.
+    pub(crate) fn status(&self, redirect: Redirect, method: &Method) -> Option<StatusCode> {
+        if !method.is_idempotent() && !self.redirect_non_idempotent {
+            return None;
+        }
+
+        let (get, other) = match redirect {
+            Redirect::TrailingSlash => self.trailing_slash,
+            Redirect::FixedPath => self.fixed_path,
+        };
+
+        match *method {
+            Method::GET | Method::HEAD => Some(get),
+            _ => Some(other),
+        }
+    }
+
+    /// Returns the `Location` of a redirect to the given path, taking into account any
+    /// prefix stripped by a parent router.
+    pub(crate) fn location(&self, req: &Request<Body>, path: &str) -> String {
+        let mut location = self.base_url.clone().unwrap_or_default();
+
+        if self.forwarded_prefix {
+            if let Some(prefix) = forwarded_prefix(req) {
+                location.push_str(prefix);
+            }
+        }
+
+        if let Some(NestedPrefix(prefix)) = req.extensions().get::<NestedPrefix>() {
+            location.push_str(prefix);
+        }
+
+        location.push_str(path);
+
+        if self.preserve_query {
+            if let Some(query) = req.uri().query() {
+                location.push('?');
+                location.push_str(query);
+            }
+        }
+
+        location
+    }
+}
+
+/// Returns the path prefix in the `X-Forwarded-Prefix` header, without a trailing slash.
+fn forwarded_prefix(req: &Request<Body>) -> Option<&str> {
+    let prefix = req.headers().get("x-forwarded-prefix")?.to_str().ok()?;
+    let prefix = prefix.trim().trim_end_matches('/');
+
+    // a prefix such as `//example.com` would redirect to another host
+    let valid = prefix.starts_with('/')
+        && !prefix.starts_with("//")
+        && !prefix.contains('\\')
+        && !prefix.contains(|c: char| c.is_ascii_whitespace());
+    valid.then_some(prefix)
+}
+
+fn redirect_status(status: StatusCode) -> StatusCode {
+    match status {
+        StatusCode::MOVED_PERMANENTLY
+        | StatusCode::FOUND
+        | StatusCode::TEMPORARY_REDIRECT
+        | StatusCode::PERMANENT_REDIRECT => status,
+        _ => panic!("invalid redirect status: {}", status),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{handler_fn, RedirectPolicy, Router};
+
+    use std::convert::Infallible;
+
+    use hyper::header::{self, HeaderValue};
+    use hyper::{Body, Method, Request, Response, StatusCode};
+
+    async fn redirect(router: &Router, req: Request<Body>) -> (StatusCode, Option<HeaderValue>) {
+        let res = router.serve(req).await.unwrap();
+        (res.status(), res.headers().get(header::LOCATION).cloned())
+    }
+
+    #[tokio::test]
+    async fn redirect_policy() {
+        let ok = handler_fn(|_| async { Ok::<_, Infallible>(Response::new(Body::empty())) });
+        let router = Router::default()
+            .get("/users", ok.clone())
+            .post("/users", ok.clone())
+            .post("/orders/", ok);
+
+        // the query string is preserved
+        let req = Request::get("/Users?page=2").body(Body::empty()).unwrap();
+        let (status, location) = redirect(&router, req).await;
+        assert_eq!(status, StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(location.unwrap(), "/users?page=2");
+
+        let req = Request::post("/orders").body(Body::empty()).unwrap();
+        let (status, location) = redirect(&router, req).await;
+        assert_eq!(status, StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(location.unwrap(), "/orders/");
+
+        let router = router.redirect_policy(
+            RedirectPolicy::default()
+                .trailing_slash_status(StatusCode::FOUND, StatusCode::TEMPORARY_REDIRECT)
+                .base_url("https://example.com/")
+                .forwarded_prefix(true)
+                .preserve_query(false)
+                .redirect_non_idempotent(false),
+        );
+
+        let req = Request::get("/users/?page=2")
+            .header("x-forwarded-prefix", "/api/")
+            .body(Body::empty())
+            .unwrap();
+        let (status, location) = redirect(&router, req).await;
+        assert_eq!(status, StatusCode::FOUND);
+        assert_eq!(location.unwrap(), "https://example.com/api/users");
+
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("/orders")
+            .body(Body::empty())
+            .unwrap();
+        let (status, location) = redirect(&router, req).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(location, None);
+
+        // prefixes that would change the host are ignored
+        let router = router.redirect_policy(RedirectPolicy::default().forwarded_prefix(true));
+        for prefix in ["//evil.com", "/\\evil.com", "/ /evil.com"] {
+            let req = Request::get("/USERS")
+                .header("x-forwarded-prefix", prefix)
+                .body(Body::empty())
+                .unwrap();
+            let (status, location) = redirect(&router, req).await;
+            assert_eq!(status, StatusCode::MOVED_PERMANENTLY);
+            assert_eq!(location.unwrap(), "/users");
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_redirect_status() {
+        let _ = RedirectPolicy::default().fixed_path_status(StatusCode::OK, StatusCode::OK);
+    }
+}
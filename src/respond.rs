@@ -0,0 +1,251 @@
+//! Responses generated by the router itself, rather than by a handler.
+use crate::fs::{accepts_html, escape_html, escape_json};
+use crate::recover::RequestParts;
+
+use std::sync::Arc;
+
+use hyper::header;
+use hyper::{Body, Method, Response, StatusCode};
+
+/// A response generated by a [`Router`](crate::Router) when no handler answers the
+/// request, see [`Router::responder`](crate::Router::responder).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BuiltinResponse {
+    /// No route matches the request, and no `NotFound` handler is set.
+    NotFound,
+    /// Routes match the path of the request, but not its method, and no
+    /// `MethodNotAllowed` handler is set.
+    MethodNotAllowed {
+        /// The methods of the routes that match the path.
+        allowed: Vec<String>,
+    },
+    /// An automatic response to an `OPTIONS` request.
+    Options {
+        /// The methods of the routes that match the path.
+        allowed: Vec<String>,
+    },
+    /// A redirect to the path with or without a trailing slash, or to the cleaned
+    /// path.
+    Redirect {
+        /// The `Location` of the redirect.
+        location: String,
+        /// The status of the redirect, one of `301 Moved Permanently`, `302 Found`,
+        /// `307 Temporary Redirect` or `308 Permanent Redirect`, see
+        /// [`RedirectPolicy`](crate::RedirectPolicy).
+        status: StatusCode,
+    },
+}
+
+impl BuiltinResponse {
+    /// The status of the response.
+    pub fn status(&self) -> StatusCode {
+        match *self {
+            BuiltinResponse::NotFound => StatusCode::NOT_FOUND,
+            BuiltinResponse::MethodNotAllowed { .. } => StatusCode::METHOD_NOT_ALLOWED,
+            BuiltinResponse::Options { .. } => StatusCode::OK,
+            BuiltinResponse::Redirect { status, .. } => status,
+        }
+    }
+
+    /// Converts into a response with the status and the `Allow` or `Location` header
+    /// of the response, but an empty body. This is the default.
+    pub fn into_response(self) -> Response<Body> {
+        self.builder().body(Body::empty()).unwrap()
+    }
+
+    /// Converts into a response with the status and the `Allow` or `Location` header
+    /// of the response, and a body describing it. The body is an HTML page if the
+    /// request explicitly accepts `text/html`, and an [RFC 7807] problem details
+    /// object of type `application/problem+json` otherwise.
+    ///
+    /// Responses to `OPTIONS` requests are successful and have an empty body.
+    /// ```rust
+    /// use httprouter::{BuiltinResponse, Router};
+    ///
+    /// let router = Router::default().responder(|res, parts| match res {
+    ///     // keep redirects short
+    ///     BuiltinResponse::Redirect { .. } => res.into_response(),
+    ///     _ => res.into_problem(&parts),
+    /// });
+    /// ```
+    ///
+    /// [RFC 7807]: https://www.rfc-editor.org/rfc/rfc7807
+    pub fn into_problem(self, parts: &RequestParts) -> Response<Body> {
+        if let BuiltinResponse::Options { .. } = self {
+            return self.into_response();
+        }
+
+        let (content_type, body) = if accepts_html(parts.headers()) {
+            ("text/html; charset=utf-8", self.html(parts))
+        } else {
+            ("application/problem+json", self.json(parts))
+        };
+
+        let len = body.len();
+        let body = if parts.method() == Method::HEAD {
+            Body::empty()
+        } else {
+            Body::from(body)
+        };
+
+        self.builder()
+            .header(header::CONTENT_TYPE, content_type)
+            .header(header::CONTENT_LENGTH, len)
+            .body(body)
+            .unwrap()
+    }
+
+    fn builder(&self) -> hyper::http::response::Builder {
+        let builder = Response::builder().status(self.status());
+        match self {
+            BuiltinResponse::NotFound => builder,
+            BuiltinResponse::MethodNotAllowed { allowed }
+            | BuiltinResponse::Options { allowed } => {
+                builder.header(header::ALLOW, allowed.join(", "))
+            }
+            BuiltinResponse::Redirect { location, .. } => {
+                builder.header(header::LOCATION, location.as_str())
+            }
+        }
+    }
+
+    /// A sentence describing the response.
+    fn detail(&self, parts: &RequestParts) -> String {
+        match self {
+            BuiltinResponse::NotFound => {
+                format!("No resource was found at {}.", parts.uri().path())
+            }
+            BuiltinResponse::MethodNotAllowed { allowed } => format!(
+                "The {} method is not allowed for {}, use one of {}.",
+                parts.method(),
+                parts.uri().path(),
+                allowed.join(", ")
+            ),
+            BuiltinResponse::Options { allowed } => {
+                format!("The allowed methods are {}.", allowed.join(", "))
+            }
+            BuiltinResponse::Redirect { location, .. } => {
+                format!("The resource has moved to {}.", location)
+            }
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        self.status().canonical_reason().unwrap_or_default()
+    }
+
+    fn json(&self, parts: &RequestParts) -> String {
+        let mut json = format!(
+            "{{\"type\":\"about:blank\",\"title\":\"{}\",\"status\":{},\"detail\":\"{}\",\"instance\":\"{}\"",
+            self.title(),
+            self.status().as_u16(),
+            escape_json(&self.detail(parts)),
+            escape_json(parts.uri().path())
+        );
+
+        match self {
+            BuiltinResponse::MethodNotAllowed { allowed }
+            | BuiltinResponse::Options { allowed } => {
+                let allowed = allowed
+                    .iter()
+                    .map(|method| format!("\"{}\"", escape_json(method)))
+                    .collect::<Vec<_>>();
+                json.push_str(&format!(",\"allowed\":[{}]", allowed.join(",")));
+            }
+            BuiltinResponse::Redirect { location, .. } => {
+                json.push_str(&format!(",\"location\":\"{}\"", escape_json(location)));
+            }
+            BuiltinResponse::NotFound => {}
+        }
+
+        json.push('}');
+        json
+    }
+
+    fn html(&self, parts: &RequestParts) -> String {
+        let title = format!("{} {}", self.status().as_u16(), self.title());
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n</head>\n<body>\n<h1>{0}</h1>\n<p>{1}</p>\n",
+            title,
+            escape_html(&self.detail(parts))
+        );
+
+        if let BuiltinResponse::Redirect { location, .. } = self {
+            let location = escape_html(location);
+            html.push_str(&format!("<p><a href=\"{0}\">{0}</a></p>\n", location));
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+/// Builds the response of the router.
+pub(crate) type Responder =
+    Arc<dyn Fn(BuiltinResponse, RequestParts) -> Response<Body> + Send + Sync>;
+
+#[cfg(test)]
+mod tests {
+    use crate::{handler_fn, Router};
+
+    use std::convert::Infallible;
+
+    use hyper::{Body, Request, Response, StatusCode};
+
+    async fn body(res: Response<Body>) -> String {
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn builtin_responses() {
+        let ok = handler_fn(|_| async { Ok::<_, Infallible>(Response::new(Body::empty())) });
+        let router = Router::default()
+            .get("/users", ok.clone())
+            .post("/users", ok);
+
+        // empty bodies by default, and a 404 for unmatched routes
+        let req = Request::get("/missing").body(Body::empty()).unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(body(res).await, "");
+
+        let router = router.problem_details();
+
+        let req = Request::get("/missing").body(Body::empty()).unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(res.headers()["content-type"], "application/problem+json");
+        assert_eq!(
+            body(res).await,
+            "{\"type\":\"about:blank\",\"title\":\"Not Found\",\"status\":404,\"detail\":\"No resource was found at /missing.\",\"instance\":\"/missing\"}"
+        );
+
+        let req = Request::delete("/users").body(Body::empty()).unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()["allow"], "GET, HEAD, POST, OPTIONS");
+        assert!(body(res)
+            .await
+            .ends_with(",\"allowed\":[\"GET\",\"HEAD\",\"POST\",\"OPTIONS\"]}"));
+
+        let req = Request::get("/users/")
+            .header("accept", "text/html,*/*;q=0.8")
+            .body(Body::empty())
+            .unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(res.headers()["location"], "/users");
+        assert_eq!(res.headers()["content-type"], "text/html; charset=utf-8");
+        assert!(body(res)
+            .await
+            .contains("<title>301 Moved Permanently</title>"));
+
+        let req = Request::options("/users").body(Body::empty()).unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()["allow"], "GET, HEAD, POST, OPTIONS");
+        assert_eq!(body(res).await, "");
+    }
+}
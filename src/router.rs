@@ -82,28 +82,51 @@
 //!    println!("{}: {}", k, v")
 //! }
 //! ```
-use crate::path::clean;
-
+use crate::constraint::{self, Constraint};
+use crate::de::{ParamsDeserializer, ParamsError};
+use crate::fs::{accepts_html, ServeFiles, StaticFiles, FILEPATH};
+use crate::group::Group;
+use crate::handle::RouterHandle;
+use crate::layer::{BoxLayer, Handler};
+use crate::nest::{Nested, NESTED_PATH};
+use crate::path::{
+    clean, decode, encode_path, encode_segment, join, normalize_encoding, DecodedPath,
+};
+use crate::recover::{CatchPanic, ErrorHandler, PanicHandler, RequestParts};
+use crate::redirect::{Redirect, RedirectPolicy, RouterRedirects};
+use crate::respond::{BuiltinResponse, Responder};
+
+use std::any::Any;
 use std::collections::HashMap;
 use std::error::Error as StdError;
 use std::fmt;
 use std::future::Future;
 use std::pin::Pin;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
+use arc_swap::ArcSwap;
 use futures_util::{future, ready};
+use hyper::body::HttpBody;
 use hyper::service::Service;
 use hyper::{header, Body, Method, Request, Response, StatusCode};
-use matchit::Node;
+use matchit::{InsertError, Node};
+use serde::Deserialize;
+use tower_layer::Layer;
 
 #[derive(Default)]
 pub struct Params {
     vec: Vec<(String, String)>,
+    // the percent-encoded value of each parameter, if it differs from the decoded value
+    raw: Vec<Option<String>>,
 }
 
 impl Params {
     /// Returns the value of the first parameter registered matched for the given key.
+    ///
+    /// Values are percent-decoded, so `/users/John%20Doe` matched by `/users/:name`
+    /// yields `John Doe`.
     pub fn get(&self, key: impl AsRef<str>) -> Option<&str> {
         self.vec
             .iter()
@@ -111,6 +134,78 @@ impl Params {
             .map(|(_, v)| v.as_str())
     }
 
+    /// Returns the value of the first parameter matched for the given key as it
+    /// appears in the request path, before percent-decoding.
+    /// ```rust
+    /// # use httprouter::Params;
+    /// # fn run(params: &Params) {
+    /// // for the route `/users/:name` and the path `/users/John%20Doe`
+    /// assert_eq!(params.get("name"), Some("John Doe"));
+    /// assert_eq!(params.get_raw("name"), Some("John%20Doe"));
+    /// # }
+    /// ```
+    pub fn get_raw(&self, key: impl AsRef<str>) -> Option<&str> {
+        let index = self.vec.iter().position(|(k, _)| k == key.as_ref())?;
+        match self.raw[index] {
+            Some(ref raw) => Some(raw),
+            None => Some(&self.vec[index].1),
+        }
+    }
+
+    /// Parses the value of the first parameter matched for the given key.
+    /// ```rust
+    /// # use httprouter::{Params, ParamsError};
+    /// # fn run(params: &Params) -> Result<(), ParamsError> {
+    /// // for the route `/orders/:id`
+    /// let id: u64 = params.parse("id")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn parse<T>(&self, key: impl AsRef<str>) -> Result<T, ParamsError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let key = key.as_ref();
+        let value = self.get(key).ok_or_else(|| ParamsError::Missing {
+            key: key.to_owned(),
+        })?;
+
+        value.parse().map_err(|err: T::Err| ParamsError::Invalid {
+            key: key.to_owned(),
+            value: value.to_owned(),
+            message: err.to_string(),
+        })
+    }
+
+    /// Deserializes the parameters into a type implementing `serde::Deserialize`.
+    ///
+    /// Structs and maps are deserialized by parameter name, tuples and sequences by
+    /// position, and primitive types from the only parameter of the route.
+    /// ```rust
+    /// # use httprouter::{Params, ParamsError};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct UserPath {
+    ///     org: String,
+    ///     id: u64,
+    /// }
+    ///
+    /// # fn run(params: &Params) -> Result<(), ParamsError> {
+    /// // for the route `/orgs/:org/users/:id`
+    /// let path: UserPath = params.deserialize()?;
+    /// let (org, id): (String, u64) = params.deserialize()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn deserialize<'de, T>(&'de self) -> Result<T, ParamsError>
+    where
+        T: Deserialize<'de>,
+    {
+        T::deserialize(ParamsDeserializer::new(&self.vec))
+    }
+
     /// Returns an iterator over the parameters in the list.
     pub fn iter(&self) -> std::slice::Iter<'_, (String, String)> {
         self.vec.iter()
@@ -121,8 +216,32 @@ impl Params {
         self.vec.iter_mut()
     }
 
+    pub(crate) fn push(&mut self, key: String, value: String) {
+        self.push_encoded(key, value, None);
+    }
+
+    /// Adds a parameter with the percent-encoded value it was decoded from, if any.
+    pub(crate) fn push_encoded(&mut self, key: String, value: String, raw: Option<String>) {
+        self.vec.push((key, value));
+        self.raw
+            .push(raw.filter(|raw| *raw != self.vec[self.vec.len() - 1].1));
+    }
+
+    /// Removes the first parameter with the given key, returning its value before
+    /// percent-decoding.
+    pub(crate) fn remove_raw(&mut self, key: &str) -> Option<String> {
+        let index = self.vec.iter().position(|(k, _)| k == key)?;
+        let (_, value) = self.vec.remove(index);
+        Some(self.raw.remove(index).unwrap_or(value))
+    }
+}
+
+impl IntoIterator for Params {
+    type Item = (String, String);
+    type IntoIter = std::vec::IntoIter<(String, String)>;
+
     /// Returns an owned iterator over the parameters in the list.
-    pub fn into_iter(self) -> std::vec::IntoIter<(String, String)> {
+    fn into_iter(self) -> Self::IntoIter {
         self.vec.into_iter()
     }
 }
@@ -160,12 +279,12 @@ pub trait HandlerError: StdError + Send + Sync + 'static {}
 impl<E> HandlerError for E where E: StdError + Send + Sync + 'static {}
 
 #[derive(Clone)]
-struct HandlerServiceImpl<S> {
+pub(crate) struct HandlerServiceImpl<S> {
     service: S,
 }
 
 impl<S> HandlerServiceImpl<S> {
-    fn new(service: S) -> Self {
+    pub(crate) fn new(service: S) -> Self {
         Self { service }
     }
 }
@@ -186,7 +305,7 @@ where
 
     fn call(&mut self, req: R) -> Self::Future {
         use futures_util::future::TryFutureExt;
-        Box::pin(self.service.call(req).map_err(|e| BoxError(Box::new(e))))
+        Box::pin(self.service.call(req).map_err(BoxError::new))
     }
 }
 
@@ -233,7 +352,7 @@ where
     }
 }
 
-trait StoredService:
+pub(crate) trait StoredService:
     Service<
         Request<Body>,
         Error = BoxError,
@@ -265,19 +384,113 @@ where
 
 impl Clone for Box<dyn StoredService> {
     fn clone(&self) -> Self {
-        self.box_clone()
+        (**self).box_clone()
+    }
+}
+
+/// The methods reported as allowed for routes registered with [`Router::any`].
+static ANY_METHODS: [Method; 9] = [
+    Method::GET,
+    Method::HEAD,
+    Method::POST,
+    Method::PUT,
+    Method::DELETE,
+    Method::CONNECT,
+    Method::OPTIONS,
+    Method::TRACE,
+    Method::PATCH,
+];
+
+/// A route waiting to be inserted into a [`Router`].
+#[derive(Clone)]
+pub(crate) struct RouteEntry {
+    // `None` for routes that match any method
+    pub(crate) method: Option<Method>,
+    pub(crate) path: String,
+    pub(crate) name: Option<String>,
+    pub(crate) service: Box<dyn StoredService>,
+    // the layers of the route, innermost first
+    pub(crate) layers: Vec<BoxLayer>,
+}
+
+impl RouteEntry {
+    pub(crate) fn new<H, F, E>(method: Method, path: String, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        Self {
+            method: Some(method),
+            path,
+            name: None,
+            service: Box::new(HandlerServiceImpl::new(handler)),
+            layers: Vec::new(),
+        }
+    }
+
+    pub(crate) fn any<H, F, E>(path: String, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        Self {
+            method: None,
+            path,
+            name: None,
+            service: Box::new(HandlerServiceImpl::new(handler)),
+            layers: Vec::new(),
+        }
+    }
+}
+
+/// A handler stored in the routing tree, along with the constraints on its parameters.
+struct Endpoint {
+    service: Box<dyn StoredService>,
+    constraints: Vec<(String, Constraint)>,
+}
+
+impl Endpoint {
+    /// Returns the first parameter that does not satisfy its constraint.
+    fn reject<'p>(&self, params: &'p [(String, String)]) -> Option<&'p (String, String)> {
+        if self.constraints.is_empty() {
+            return None;
+        }
+
+        params.iter().find(|(key, value)| {
+            self.constraints
+                .iter()
+                .any(|(name, constraint)| name == key && !constraint.accepts(value))
+        })
     }
 }
 
 pub struct Router {
-    trees: HashMap<Method, Node<Box<dyn StoredService>>>,
+    trees: HashMap<Method, Node<Endpoint>>,
+    any: Node<Endpoint>,
+    routes: Vec<RouteEntry>,
+    // the routes of nested routers, keyed by the path they are mounted at
+    mounted: HashMap<String, Mount>,
+    names: HashMap<String, String>,
     redirect_trailing_slash: bool,
     redirect_fixed_path: bool,
     handle_method_not_allowed: bool,
     handle_options: bool,
+    auto_head: bool,
+    use_raw_path: bool,
+    bad_request_on_constraint_failure: bool,
+    redirect_policy: Arc<RedirectPolicy>,
     global_options: Option<Box<dyn StoredService>>,
     not_found: Option<Box<dyn StoredService>>,
     method_not_allowed: Option<Box<dyn StoredService>>,
+    spa_fallback: Option<Box<dyn StoredService>>,
+    // the layers applied to every route, and whether they apply to the fallback
+    // handlers as well, in the order they were added
+    layers: Vec<(BoxLayer, bool)>,
+    panic_handler: Option<PanicHandler>,
+    error_handler: Option<ErrorHandler>,
+    responder: Option<Responder>,
 }
 
 impl Router {
@@ -292,253 +505,1492 @@ impl Router {
     ///         Ok::<_, Infallible>(Response::new(Body::from("I am a teapot!")))
     ///     }));
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the route could not be registered. See [`Router::try_handle`] for a
+    /// non-panicking version.
     pub fn handle<H, F, E>(mut self, path: impl Into<String>, method: Method, handler: H) -> Self
     where
         H: HandlerService<F, E>,
         F: HandlerFuture<E>,
         E: HandlerError,
     {
-        let path = path.into();
-        if !path.starts_with('/') {
-            panic!("expect path beginning with '/', found: '{}'", path);
+        if let Err(err) = self.try_handle(path, method, handler) {
+            panic!("{}", err);
         }
 
-        self.trees
-            .entry(method)
-            .or_insert_with(Node::default)
-            .insert(path, Box::new(HandlerServiceImpl::new(handler)))
-            .unwrap();
-
         self
     }
 
-    /// TODO
-    pub fn serve_files() {
-        unimplemented!()
-    }
-
-    /// Register a handler for `GET` requests
-    pub fn get<H, F, E>(self, path: impl Into<String>, handler: H) -> Self
+    /// Register a handler for the given path and method, returning an error if the
+    /// route could not be registered.
+    ///
+    /// Unlike [`Router::handle`], this does not consume the router, so registration
+    /// errors can be collected and reported together:
+    /// ```rust
+    /// use httprouter::{Router, RouteError, handler_fn};
+    /// use hyper::{Response, Body, Method};
+    /// use std::convert::Infallible;
+    ///
+    /// let handler = handler_fn(|_| async {
+    ///     Ok::<_, Infallible>(Response::new(Body::empty()))
+    /// });
+    ///
+    /// let mut router = Router::default();
+    /// let errors: Vec<RouteError> = ["/users/:id", "/users/:name", "files/*path"]
+    ///     .iter()
+    ///     .filter_map(|path| router.try_handle(*path, Method::GET, handler.clone()).err())
+    ///     .collect();
+    ///
+    /// assert_eq!(errors.len(), 2);
+    /// ```
+    pub fn try_handle<H, F, E>(
+        &mut self,
+        path: impl Into<String>,
+        method: Method,
+        handler: H,
+    ) -> Result<&mut Self, RouteError>
     where
         H: HandlerService<F, E>,
         F: HandlerFuture<E>,
         E: HandlerError,
     {
-        self.handle(path, Method::GET, handler)
+        self.insert(RouteEntry::new(method, path.into(), handler))?;
+        Ok(self)
     }
 
-    /// Register a handler for `HEAD` requests
-    pub fn head<H, F, E>(self, path: impl Into<String>, handler: H) -> Self
+    /// Register a named handler for the given path and method. The name can later be
+    /// used to build URLs for the route with [`Router::url_for`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the route could not be registered. See [`Router::try_handle_named`]
+    /// for a non-panicking version.
+    pub fn handle_named<H, F, E>(
+        mut self,
+        name: impl Into<String>,
+        path: impl Into<String>,
+        method: Method,
+        handler: H,
+    ) -> Self
     where
         H: HandlerService<F, E>,
         F: HandlerFuture<E>,
         E: HandlerError,
     {
-        self.handle(path, Method::HEAD, handler)
-    }
+        if let Err(err) = self.try_handle_named(name, path, method, handler) {
+            panic!("{}", err);
+        }
 
-    /// Register a handler for `OPTIONS` requests
-    pub fn options<H, F, E>(self, path: impl Into<String>, handler: H) -> Self
-    where
-        H: HandlerService<F, E>,
-        F: HandlerFuture<E>,
-        E: HandlerError,
-    {
-        self.handle(path, Method::OPTIONS, handler)
+        self
     }
 
-    /// Register a handler for `POST` requests
-    pub fn post<H, F, E>(self, path: impl Into<String>, handler: H) -> Self
+    /// Register a named handler for the given path and method, returning an error if
+    /// the route could not be registered. See [`Router::handle_named`].
+    pub fn try_handle_named<H, F, E>(
+        &mut self,
+        name: impl Into<String>,
+        path: impl Into<String>,
+        method: Method,
+        handler: H,
+    ) -> Result<&mut Self, RouteError>
     where
         H: HandlerService<F, E>,
         F: HandlerFuture<E>,
         E: HandlerError,
     {
-        self.handle(path, Method::POST, handler)
+        let mut route = RouteEntry::new(method, path.into(), handler);
+        route.name = Some(name.into());
+        self.insert(route)?;
+        Ok(self)
     }
 
-    /// Register a handler for `PUT` requests
-    pub fn put<H, F, E>(self, path: impl Into<String>, handler: H) -> Self
+    /// Register a handler for the given path and method, wrapped with a
+    /// [`tower::Layer`] that only applies to this route, such as a longer timeout for an
+    /// upload endpoint. The layer is stored with the handler in the routing tree, so
+    /// other routes and unmatched requests are unaffected. Layers added with
+    /// [`Router::layer`] and [`Router::route_layer`] wrap the route's own layer.
+    /// ```rust
+    /// use httprouter::{Router, handler_fn};
+    /// use hyper::{Response, Body, Method};
+    /// use std::convert::Infallible;
+    /// use std::time::Duration;
+    /// use tower::timeout::TimeoutLayer;
+    ///
+    /// let router = Router::default().handle_layered(
+    ///     "/upload",
+    ///     Method::POST,
+    ///     handler_fn(|_| async { Ok::<_, Infallible>(Response::new(Body::empty())) }),
+    ///     TimeoutLayer::new(Duration::from_secs(300)),
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the route could not be registered. See [`Router::try_handle_layered`]
+    /// for a non-panicking version.
+    ///
+    /// [`tower::Layer`]: https://docs.rs/tower/latest/tower/trait.Layer.html
+    pub fn handle_layered<H, F, E, L>(
+        mut self,
+        path: impl Into<String>,
+        method: Method,
+        handler: H,
+        layer: L,
+    ) -> Self
     where
         H: HandlerService<F, E>,
         F: HandlerFuture<E>,
         E: HandlerError,
+        L: Layer<Handler> + Send + Sync + 'static,
+        L::Service:
+            Service<Request<Body>, Response = Response<Body>> + Clone + Send + Sync + 'static,
+        <L::Service as Service<Request<Body>>>::Future: Send + Sync + 'static,
+        <L::Service as Service<Request<Body>>>::Error: Into<Box<dyn StdError + Send + Sync>>,
     {
-        self.handle(path, Method::PUT, handler)
-    }
+        if let Err(err) = self.try_handle_layered(path, method, handler, layer) {
+            panic!("{}", err);
+        }
 
-    /// Register a handler for `PATCH` requests
-    pub fn patch<H, F, E>(self, path: impl Into<String>, handler: H) -> Self
-    where
-        H: HandlerService<F, E>,
-        F: HandlerFuture<E>,
-        E: HandlerError,
-    {
-        self.handle(path, Method::PATCH, handler)
+        self
     }
 
-    /// Register a handler for `DELETE` requests
-    pub fn delete<H, F, E>(self, path: impl Into<String>, handler: H) -> Self
+    /// Register a handler for the given path and method, wrapped with a layer that only
+    /// applies to this route, returning an error if the route could not be registered.
+    /// See [`Router::handle_layered`].
+    pub fn try_handle_layered<H, F, E, L>(
+        &mut self,
+        path: impl Into<String>,
+        method: Method,
+        handler: H,
+        layer: L,
+    ) -> Result<&mut Self, RouteError>
     where
         H: HandlerService<F, E>,
         F: HandlerFuture<E>,
         E: HandlerError,
+        L: Layer<Handler> + Send + Sync + 'static,
+        L::Service:
+            Service<Request<Body>, Response = Response<Body>> + Clone + Send + Sync + 'static,
+        <L::Service as Service<Request<Body>>>::Future: Send + Sync + 'static,
+        <L::Service as Service<Request<Body>>>::Error: Into<Box<dyn StdError + Send + Sync>>,
     {
-        self.handle(path, Method::DELETE, handler)
+        let mut route = RouteEntry::new(method, path.into(), handler);
+        route.layers.push(BoxLayer::new(layer));
+        self.insert(route)?;
+        Ok(self)
     }
 
-    /// Enables automatic redirection if the current route can't be matched but a
-    /// handler for the path with (without) the trailing slash exists.
-    /// For example if `/foo/` is requested but a route only exists for `/foo`, the
-    /// client is redirected to `/foo` with HTTP status code 301 for `GET` requests
-    /// and 307 for all other request methods.
-    pub fn redirect_trailing_slash(mut self) -> Self {
-        self.redirect_trailing_slash = true;
-        self
-    }
+    pub(crate) fn insert(&mut self, route: RouteEntry) -> Result<(), RouteError> {
+        let RouteEntry {
+            method,
+            path,
+            name,
+            service,
+            layers,
+        } = route;
 
-    /// If enabled, the router tries to fix the current request path, if no
-    /// handle is registered for it.
-    /// First superfluous path elements like `../` or `//` are removed.
-    /// Afterwards the router does a case-insensitive lookup of the cleaned path.
-    /// If a handle can be found for this route, the router makes a redirection
-    /// to the corrected path with status code 301 for `GET` requests and 307 for
-    /// all other request methods.
-    /// For example `/FOO` and `/..//Foo` could be redirected to `/foo`.
-    /// `redirect_trailing_slash` is independent of this option.
-    pub fn redirect_fixed_path(mut self) -> Self {
-        self.redirect_fixed_path = true;
-        self
-    }
+        if !path.starts_with('/') {
+            return Err(RouteError::InvalidPath {
+                path,
+                reason: "path must begin with '/'".into(),
+            });
+        }
 
-    /// If enabled, the router checks if another method is allowed for the
-    /// current route, if the current request can not be routed.
-    /// If this is the case, the request is answered with `MethodNotAllowed`
-    /// and HTTP status code 405.
-    /// If no other Method is allowed, the request is delegated to the `NotFound`
-    /// handler.
-    pub fn handle_method_not_allowed(mut self) -> Self {
-        self.handle_method_not_allowed = true;
-        self
-    }
+        if let Some(method) = method.as_ref() {
+            if self
+                .routes
+                .iter()
+                .any(|route| route.method.as_ref() == Some(method) && route.path == path)
+            {
+                return Err(RouteError::Duplicate {
+                    path,
+                    method: method.clone(),
+                });
+            }
+        }
 
-    /// If enabled, the router automatically replies to `OPTIONS` requests.
-    /// Custom `OPTIONS` handlers take priority over automatic replies.
-    pub fn handle_options(mut self) -> Self {
-        self.handle_options = true;
-        self
-    }
+        if let Some(name) = name.as_ref().filter(|name| self.names.contains_key(*name)) {
+            return Err(RouteError::DuplicateName {
+                name: name.clone(),
+                path,
+            });
+        }
 
-    /// An optional handler that is called on automatic `OPTIONS` requests.
-    /// The handler is only called if `handle_options` is true and no `OPTIONS`
-    /// handler for the specific path was set.
-    /// The `Allowed` header is set before calling the handler.
-    pub fn global_options<H, F, E>(mut self, handler: H) -> Self
-    where
-        H: HandlerService<F, E>,
-        F: HandlerFuture<E>,
-        E: HandlerError,
-    {
-        self.global_options = Some(Box::new(HandlerServiceImpl::new(handler)));
-        self
-    }
+        let (pattern, constraints) = match constraint::parse(&path) {
+            Ok(parsed) => parsed,
+            Err(reason) => return Err(RouteError::InvalidPath { path, reason }),
+        };
 
-    /// Configurable handler which is called when no matching route is
-    /// found.
-    pub fn not_found<H, F, E>(mut self, handler: H) -> Self
-    where
-        H: HandlerService<F, E>,
-        F: HandlerFuture<E>,
-        E: HandlerError,
-    {
-        self.not_found = Some(Box::new(HandlerServiceImpl::new(handler)));
-        self
-    }
+        // the route's own layers are wrapped by the layers of the router
+        let layered = layers
+            .iter()
+            .fold(service.clone(), |service, layer| layer.apply(service));
+
+        let endpoint = Endpoint {
+            service: self
+                .layers
+                .iter()
+                .fold(layered, |service, (layer, _)| layer.apply(service)),
+            constraints,
+        };
 
-    /// A configurable handler which is called when a request
-    /// cannot be routed and `handle_method_not_allowed` is true.
-    /// The `Allow` header with allowed request methods is set before the handler
-    /// is called.
-    pub fn method_not_allowed<H, F, E>(mut self, handler: H) -> Self
-    where
-        H: HandlerService<F, E>,
-        F: HandlerFuture<E>,
-        E: HandlerError,
-    {
-        self.method_not_allowed = Some(Box::new(HandlerServiceImpl::new(handler)));
-        self
+        let tree = match method {
+            Some(ref method) => self.trees.entry(method.clone()).or_default(),
+            None => &mut self.any,
+        };
+
+        tree.insert(pattern.clone(), endpoint)
+            .map_err(|err| RouteError::from_insert(path.clone(), err))?;
+
+        if let Some(ref name) = name {
+            self.names.insert(name.clone(), pattern);
+        }
+
+        self.routes.push(RouteEntry {
+            method,
+            path,
+            name,
+            service,
+            layers,
+        });
+        Ok(())
     }
 
-    /// Returns a list of the allowed methods for a specific path
+    /// Returns the routes registered on the router, ordered by path and then by method.
+    ///
+    /// Routes of nested routers are included with their full path, while nested
+    /// services are reported as a single route for any method, with an unnamed
+    /// catch-all such as `/legacy/*`, see [`Route::is_mount`].
     /// ```rust
     /// use httprouter::{Router, handler_fn};
     /// use hyper::{Response, Body, Method};
     /// use std::convert::Infallible;
     ///
+    /// let handler = handler_fn(|_| async {
+    ///     Ok::<_, Infallible>(Response::new(Body::empty()))
+    /// });
+    ///
     /// let router = Router::default()
-    ///     .get("/home", handler_fn(|_| async {
-    ///         Ok::<_, Infallible>(Response::new(Body::from("Welcome!")))
-    ///     }))
-    ///     .post("/home", handler_fn(|_| async {
-    ///         Ok::<_, Infallible>(Response::new(Body::from("{{ message: \"Welcome!\" }}")))
-    ///     }));
+    ///     .post("/users", handler.clone())
+    ///     .handle_named("user_profile", "/users/:id", Method::GET, handler.clone())
+    ///     .get("/users", handler);
     ///
-    /// let allowed = router.allowed("/home");
-    /// assert!(allowed.contains(&"GET"));
-    /// assert!(allowed.contains(&"POST"));
-    /// assert!(allowed.contains(&"OPTIONS"));
-    /// # assert_eq!(allowed.len(), 3);
+    /// for route in router.routes() {
+    ///     println!("{}", route);
+    /// }
+    ///
+    /// let routes = router.routes().map(|route| route.to_string()).collect::<Vec<_>>();
+    /// assert_eq!(routes, ["GET /users", "POST /users", "GET /users/:id (user_profile)"]);
     /// ```
-    pub fn allowed(&self, path: impl Into<String>) -> Vec<&str> {
-        let path = path.into();
-        let mut allowed = match path.as_ref() {
-            "*" => {
-                let mut allowed = Vec::with_capacity(self.trees.len());
-                for method in self
-                    .trees
-                    .keys()
-                    .filter(|&method| method != Method::OPTIONS)
-                {
-                    allowed.push(method.as_ref());
+    pub fn routes(&self) -> impl Iterator<Item = Route> {
+        let mut routes = Vec::with_capacity(self.routes.len());
+        for route in self.routes.iter() {
+            match self.mounted.get(&route.path) {
+                Some(mount) => routes.extend(mount.routes.iter().cloned()),
+                None => {
+                    let mount = route.path.strip_suffix(NESTED_PATH);
+                    routes.push(Route {
+                        method: route.method.clone(),
+                        path: mount.unwrap_or(&route.path).to_owned(),
+                        name: route.name.clone(),
+                        mount: mount.is_some(),
+                    });
                 }
-                allowed
             }
-            _ => self
-                .trees
-                .keys()
-                .filter(|&method| method != Method::OPTIONS)
-                .filter(|&method| {
-                    self.trees
-                        .get(method)
-                        .map(|node| node.at(&path).is_ok())
-                        .unwrap_or(false)
+        }
+
+        routes.sort_by(|a, b| {
+            let (a_method, b_method) = (
+                a.method().map(Method::as_str),
+                b.method().map(Method::as_str),
+            );
+            a.path.cmp(&b.path).then_with(|| a_method.cmp(&b_method))
+        });
+
+        routes.into_iter()
+    }
+
+    /// Builds the path of the route registered with the given name, substituting
+    /// its parameters with the given values.
+    ///
+    /// Parameter values are percent-encoded, and every parameter of the route must
+    /// be supplied.
+    /// ```rust
+    /// use httprouter::{Router, UrlError, handler_fn};
+    /// use hyper::{Response, Body, Method};
+    /// use std::convert::Infallible;
+    ///
+    /// let handler = handler_fn(|_| async {
+    ///     Ok::<_, Infallible>(Response::new(Body::empty()))
+    /// });
+    ///
+    /// let router = Router::default()
+    ///     .handle_named("user_profile", "/users/:id", Method::GET, handler.clone())
+    ///     .handle_named("files", "/files/*path", Method::GET, handler);
+    ///
+    /// assert_eq!(router.url_for("user_profile", &[("id", "42")]).unwrap(), "/users/42");
+    /// assert_eq!(
+    ///     router.url_for("files", &[("path", "docs/read me.md")]).unwrap(),
+    ///     "/files/docs/read%20me.md"
+    /// );
+    /// assert!(matches!(
+    ///     router.url_for("user_profile", &[]),
+    ///     Err(UrlError::MissingParam { .. })
+    /// ));
+    /// ```
+    pub fn url_for(&self, name: &str, params: &[(&str, &str)]) -> Result<String, UrlError> {
+        let pattern = self.names.get(name).ok_or_else(|| UrlError::UnknownRoute {
+            name: name.to_owned(),
+        })?;
+
+        let param = |key: &str| {
+            params
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| *v)
+                .ok_or_else(|| UrlError::MissingParam {
+                    name: name.to_owned(),
+                    param: key.to_owned(),
                 })
-                .map(AsRef::as_ref)
-                .collect::<Vec<_>>(),
         };
 
-        if !allowed.is_empty() {
-            allowed.push(Method::OPTIONS.as_ref())
+        let mut url = String::with_capacity(pattern.len());
+        let mut rest = pattern.as_str();
+        while let Some(i) = rest.find(&[':', '*'][..]) {
+            url.push_str(&rest[..i]);
+            let end = rest[i..].find('/').map_or(rest.len(), |end| i + end);
+            let key = &rest[i + 1..end];
+
+            if rest.as_bytes()[i] == b':' {
+                url.push_str(&encode_segment(param(key)?));
+            } else {
+                // catch-all values begin with the slash preceding the parameter
+                let value = param(key)?;
+                url.push_str(&encode_path(value.trim_start_matches('/')));
+            }
+
+            rest = &rest[end..];
         }
 
-        allowed
+        url.push_str(rest);
+        Ok(url)
     }
-}
 
-impl Default for Router {
-    fn default() -> Self {
-        Self {
-            trees: HashMap::new(),
-            redirect_trailing_slash: true,
-            redirect_fixed_path: true,
-            handle_method_not_allowed: true,
-            handle_options: true,
-            global_options: None,
+    /// Register a group of routes that share a common path prefix.
+    ///
+    /// Paths registered on the group are joined with the prefix, so `/api/v2` and
+    /// `/users/:id` become `/api/v2/users/:id`. An empty path registers the prefix
+    /// itself, and groups can be nested.
+    /// ```rust
+    /// use httprouter::{Router, handler_fn};
+    /// use hyper::{Response, Body};
+    /// use std::convert::Infallible;
+    ///
+    /// let handler = handler_fn(|_| async {
+    ///     Ok::<_, Infallible>(Response::new(Body::empty()))
+    /// });
+    ///
+    /// let router = Router::default().group("/api/v2", |g| {
+    ///     g.get("/users/:id", handler.clone())
+    ///         .post("/users", handler.clone())
+    ///         .group("/admin", |g| g.get("/stats", handler.clone()))
+    /// });
+    ///
+    /// assert!(router.allowed("/api/v2/users/42").contains(&"GET"));
+    /// assert!(router.allowed("/api/v2/admin/stats").contains(&"GET"));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the routes could not be registered. See [`Router::try_group`]
+    /// for a non-panicking version.
+    pub fn group<F>(mut self, prefix: impl Into<String>, f: F) -> Self
+    where
+        F: FnOnce(Group) -> Group,
+    {
+        if let Err(errors) = self.try_group(prefix, f) {
+            panic!("{}", errors[0]);
+        }
+
+        self
+    }
+
+    /// Register a group of routes that share a common path prefix, returning every
+    /// route that could not be registered. See [`Router::group`].
+    pub fn try_group<F>(
+        &mut self,
+        prefix: impl Into<String>,
+        f: F,
+    ) -> Result<&mut Self, Vec<RouteError>>
+    where
+        F: FnOnce(Group) -> Group,
+    {
+        let errors = f(Group::new(prefix.into()))
+            .into_routes()
+            .into_iter()
+            .filter_map(|route| self.insert(route).err())
+            .collect::<Vec<_>>();
+
+        if errors.is_empty() {
+            Ok(self)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Mount a router under the given path prefix.
+    ///
+    /// Requests for any path under the prefix are forwarded to the nested router,
+    /// which sees the request URI with the prefix stripped. Parameters captured by
+    /// the prefix are available to the nested router's handlers, alongside the
+    /// nested route's own parameters.
+    ///
+    /// Named routes of the nested router are registered on this router under their
+    /// full path, so [`Router::url_for`] builds URLs that include the prefix.
+    /// ```rust
+    /// use httprouter::{Router, handler_fn};
+    /// use hyper::{Response, Body, Method};
+    /// use std::convert::Infallible;
+    ///
+    /// let handler = handler_fn(|_| async {
+    ///     Ok::<_, Infallible>(Response::new(Body::empty()))
+    /// });
+    ///
+    /// let admin = Router::default()
+    ///     .handle_named("admin_user", "/users/:id", Method::GET, handler.clone());
+    ///
+    /// // `GET /tenants/acme/admin/users/1` is handled by `admin` as `GET /users/1`
+    /// let router = Router::default().nest("/tenants/:tenant/admin", admin);
+    ///
+    /// assert_eq!(
+    ///     router.url_for("admin_user", &[("tenant", "acme"), ("id", "1")]).unwrap(),
+    ///     "/tenants/acme/admin/users/1"
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the prefix could not be registered. See [`Router::try_nest`] for a
+    /// non-panicking version.
+    pub fn nest(mut self, prefix: impl Into<String>, router: Router) -> Self {
+        if let Err(err) = self.try_nest(prefix, router) {
+            panic!("{}", err);
+        }
+
+        self
+    }
+
+    /// Mount a router under the given path prefix, returning an error if the prefix
+    /// could not be registered, or if a route of the nested router has the same name
+    /// as a route of this router. See [`Router::nest`].
+    pub fn try_nest(
+        &mut self,
+        prefix: impl Into<String>,
+        router: Router,
+    ) -> Result<&mut Self, RouteError> {
+        let prefix = prefix.into();
+        let routes = router
+            .routes()
+            .map(|route| Route {
+                path: join(&prefix, &route.path),
+                ..route
+            })
+            .collect();
+
+        // names map to patterns, without the constraints of the prefix
+        let pattern = constraint::parse(&prefix).map_or_else(|_| prefix.clone(), |(p, _)| p);
+        let names = router
+            .names
+            .iter()
+            .map(|(name, path)| (name.clone(), join(&pattern, path)))
+            .collect::<Vec<_>>();
+
+        let mount = Mount { routes, names };
+        self.check_names(&mount)?;
+        self.try_nest_service(prefix.as_str(), RouterService::new(router))?;
+        self.names.extend(mount.names.iter().cloned());
+        self.mounted.insert(nested_path(&prefix), mount);
+        Ok(self)
+    }
+
+    /// Registers every route, or none of them if any could not be registered.
+    fn insert_all(
+        &mut self,
+        routes: impl IntoIterator<Item = RouteEntry>,
+    ) -> Result<(), RouteError> {
+        let len = self.routes.len();
+        for route in routes {
+            if let Err(err) = self.insert(route) {
+                // routes can't be removed from the trees, so they are rebuilt instead
+                if self.routes.len() > len {
+                    *self = self
+                        .rebuild(self.routes[..len].to_vec())
+                        .expect("registered routes are valid");
+                }
+
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns an error if a route of the nested router has the same name as a route
+    /// of this router.
+    fn check_names(&self, mount: &Mount) -> Result<(), RouteError> {
+        match mount
+            .names
+            .iter()
+            .find(|(name, _)| self.names.contains_key(name))
+        {
+            Some((name, path)) => Err(RouteError::DuplicateName {
+                name: name.clone(),
+                path: path.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Mount a service under the given path prefix. See [`Router::nest`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the prefix could not be registered. See [`Router::try_nest_service`]
+    /// for a non-panicking version.
+    pub fn nest_service<H, F, E>(mut self, prefix: impl Into<String>, service: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        if let Err(err) = self.try_nest_service(prefix, service) {
+            panic!("{}", err);
+        }
+
+        self
+    }
+
+    /// Mount a service under the given path prefix, returning an error if the prefix
+    /// could not be registered. See [`Router::nest`].
+    pub fn try_nest_service<H, F, E>(
+        &mut self,
+        prefix: impl Into<String>,
+        service: H,
+    ) -> Result<&mut Self, RouteError>
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        let path = nested_path(&prefix.into());
+        self.insert(RouteEntry::any(path, Nested::new(service)))?;
+        Ok(self)
+    }
+
+    /// Register a handler for the given path that matches requests of any method.
+    ///
+    /// Handlers registered for a specific method on the same path take precedence.
+    /// `OPTIONS` requests are answered automatically, or by the
+    /// [`Router::global_options`] handler, unless an `OPTIONS` handler is registered
+    /// for the path.
+    /// ```rust
+    /// use httprouter::{Router, handler_fn};
+    /// use hyper::{Response, Body};
+    /// use std::convert::Infallible;
+    ///
+    /// let router = Router::default()
+    ///     .any("/proxy/*path", handler_fn(|_| async {
+    ///         Ok::<_, Infallible>(Response::new(Body::from("proxied")))
+    ///     }));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the route could not be registered. See [`Router::try_any`] for a
+    /// non-panicking version.
+    pub fn any<H, F, E>(mut self, path: impl Into<String>, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        if let Err(err) = self.try_any(path, handler) {
+            panic!("{}", err);
+        }
+
+        self
+    }
+
+    /// Register a handler for the given path that matches requests of any method,
+    /// returning an error if the route could not be registered. See [`Router::any`].
+    pub fn try_any<H, F, E>(
+        &mut self,
+        path: impl Into<String>,
+        handler: H,
+    ) -> Result<&mut Self, RouteError>
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.insert(RouteEntry::any(path.into(), handler))?;
+        Ok(self)
+    }
+
+    /// Register a handler for the given path and each of the given methods, including
+    /// extension methods.
+    /// ```rust
+    /// use httprouter::{Router, handler_fn};
+    /// use hyper::{Response, Body, Method};
+    /// use std::convert::Infallible;
+    ///
+    /// let propfind = Method::from_bytes(b"PROPFIND").unwrap();
+    ///
+    /// let router = Router::default()
+    ///     .methods(&[Method::GET, propfind], "/dav/*path", handler_fn(|_| async {
+    ///         Ok::<_, Infallible>(Response::new(Body::empty()))
+    ///     }));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the route could not be registered. See [`Router::try_methods`] for a
+    /// non-panicking version.
+    pub fn methods<H, F, E>(
+        mut self,
+        methods: &[Method],
+        path: impl Into<String>,
+        handler: H,
+    ) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        if let Err(err) = self.try_methods(methods, path, handler) {
+            panic!("{}", err);
+        }
+
+        self
+    }
+
+    /// Register a handler for the given path and each of the given methods, returning
+    /// an error if any of the routes could not be registered, in which case none of
+    /// them are. See [`Router::methods`].
+    pub fn try_methods<H, F, E>(
+        &mut self,
+        methods: &[Method],
+        path: impl Into<String>,
+        handler: H,
+    ) -> Result<&mut Self, RouteError>
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        let path = path.into();
+        self.insert_all(
+            methods
+                .iter()
+                .map(|method| RouteEntry::new(method.clone(), path.clone(), handler.clone())),
+        )?;
+        Ok(self)
+    }
+
+    /// Serve files from the given root directory. The path must end with `/*filepath`,
+    /// and files are then served from the local path `root/<filepath>`. The root can
+    /// be configured further with [`StaticFiles`](crate::StaticFiles), which can also
+    /// serve files embedded in the binary, or from any other
+    /// [`FileSource`](crate::FileSource).
+    ///
+    /// `GET` and `HEAD` requests are handled, with support for conditional and range
+    /// requests, validated against the `Last-Modified` date and `ETag` of each file.
+    /// Paths are cleaned before being joined onto the root, and files outside
+    /// of the root, including through symbolic links, are never served.
+    ///
+    /// Requests for a directory are answered with its `index.html`. If
+    /// [`Router::redirect_trailing_slash`] is enabled, directories are first redirected
+    /// to their path with a trailing slash, and files to their path without one.
+    /// ```rust
+    /// use httprouter::Router;
+    ///
+    /// // `GET /static/css/app.css` serves the file `./public/css/app.css`
+    /// let router = Router::default().serve_files("/static/*filepath", "./public");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the route could not be registered. See [`Router::try_serve_files`] for
+    /// a non-panicking version.
+    pub fn serve_files(mut self, path: impl Into<String>, files: impl Into<StaticFiles>) -> Self {
+        if let Err(err) = self.try_serve_files(path, files) {
+            panic!("{}", err);
+        }
+
+        self
+    }
+
+    /// Serve files from the given root directory, returning an error if the route could
+    /// not be registered. See [`Router::serve_files`].
+    pub fn try_serve_files(
+        &mut self,
+        path: impl Into<String>,
+        files: impl Into<StaticFiles>,
+    ) -> Result<&mut Self, RouteError> {
+        let path = path.into();
+        if !path.ends_with(&["/*", FILEPATH].concat()) {
+            return Err(RouteError::InvalidPath {
+                path,
+                reason: "path must end with /*filepath".into(),
+            });
+        }
+
+        let files = ServeFiles::new(files.into());
+        self.insert_all(vec![
+            RouteEntry::new(Method::GET, path.clone(), files.clone()),
+            RouteEntry::new(Method::HEAD, path, files),
+        ])?;
+        Ok(self)
+    }
+
+    /// Answer page requests that don't match any route with the index file of the given
+    /// static files, `index.html` by default, so that a single-page application can
+    /// handle its own client-side routes.
+    ///
+    /// The fallback only applies to `GET` and `HEAD` requests that explicitly accept
+    /// `text/html`, as browsers do when navigating to a page. Requests for scripts,
+    /// images, or API responses, including missing files under a
+    /// [`Router::serve_files`] route, are still passed to the `NotFound` handler. Path
+    /// redirects and `405 Method Not Allowed` responses take precedence over the
+    /// fallback.
+    /// ```rust
+    /// use httprouter::{Router, handler_fn};
+    /// use hyper::{Response, Body};
+    /// use std::convert::Infallible;
+    ///
+    /// // `GET /dashboard/settings` serves the file `./dist/index.html`
+    /// let router = Router::default()
+    ///     .get("/api/users", handler_fn(|_| async {
+    ///         Ok::<_, Infallible>(Response::new(Body::from("[]")))
+    ///     }))
+    ///     .serve_files("/static/*filepath", "./dist")
+    ///     .spa_fallback("./dist");
+    /// ```
+    pub fn spa_fallback(mut self, files: impl Into<StaticFiles>) -> Self {
+        let files = ServeFiles::fallback(files.into());
+        self.spa_fallback = Some(self.fallback(HandlerServiceImpl::new(files)));
+        self
+    }
+
+    /// Register a handler for `GET` requests
+    pub fn get<H, F, E>(self, path: impl Into<String>, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.handle(path, Method::GET, handler)
+    }
+
+    /// Register a handler for `HEAD` requests
+    pub fn head<H, F, E>(self, path: impl Into<String>, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.handle(path, Method::HEAD, handler)
+    }
+
+    /// Register a handler for `OPTIONS` requests
+    pub fn options<H, F, E>(self, path: impl Into<String>, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.handle(path, Method::OPTIONS, handler)
+    }
+
+    /// Register a handler for `POST` requests
+    pub fn post<H, F, E>(self, path: impl Into<String>, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.handle(path, Method::POST, handler)
+    }
+
+    /// Register a handler for `PUT` requests
+    pub fn put<H, F, E>(self, path: impl Into<String>, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.handle(path, Method::PUT, handler)
+    }
+
+    /// Register a handler for `PATCH` requests
+    pub fn patch<H, F, E>(self, path: impl Into<String>, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.handle(path, Method::PATCH, handler)
+    }
+
+    /// Register a handler for `DELETE` requests
+    pub fn delete<H, F, E>(self, path: impl Into<String>, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.handle(path, Method::DELETE, handler)
+    }
+
+    /// Register a handler for `GET` requests, returning an error if the route could
+    /// not be registered. See [`Router::try_handle`].
+    pub fn try_get<H, F, E>(
+        &mut self,
+        path: impl Into<String>,
+        handler: H,
+    ) -> Result<&mut Self, RouteError>
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.try_handle(path, Method::GET, handler)
+    }
+
+    /// Register a handler for `HEAD` requests, returning an error if the route could
+    /// not be registered. See [`Router::try_handle`].
+    pub fn try_head<H, F, E>(
+        &mut self,
+        path: impl Into<String>,
+        handler: H,
+    ) -> Result<&mut Self, RouteError>
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.try_handle(path, Method::HEAD, handler)
+    }
+
+    /// Register a handler for `OPTIONS` requests, returning an error if the route could
+    /// not be registered. See [`Router::try_handle`].
+    pub fn try_options<H, F, E>(
+        &mut self,
+        path: impl Into<String>,
+        handler: H,
+    ) -> Result<&mut Self, RouteError>
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.try_handle(path, Method::OPTIONS, handler)
+    }
+
+    /// Register a handler for `POST` requests, returning an error if the route could
+    /// not be registered. See [`Router::try_handle`].
+    pub fn try_post<H, F, E>(
+        &mut self,
+        path: impl Into<String>,
+        handler: H,
+    ) -> Result<&mut Self, RouteError>
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.try_handle(path, Method::POST, handler)
+    }
+
+    /// Register a handler for `PUT` requests, returning an error if the route could
+    /// not be registered. See [`Router::try_handle`].
+    pub fn try_put<H, F, E>(
+        &mut self,
+        path: impl Into<String>,
+        handler: H,
+    ) -> Result<&mut Self, RouteError>
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.try_handle(path, Method::PUT, handler)
+    }
+
+    /// Register a handler for `PATCH` requests, returning an error if the route could
+    /// not be registered. See [`Router::try_handle`].
+    pub fn try_patch<H, F, E>(
+        &mut self,
+        path: impl Into<String>,
+        handler: H,
+    ) -> Result<&mut Self, RouteError>
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.try_handle(path, Method::PATCH, handler)
+    }
+
+    /// Register a handler for `DELETE` requests, returning an error if the route could
+    /// not be registered. See [`Router::try_handle`].
+    pub fn try_delete<H, F, E>(
+        &mut self,
+        path: impl Into<String>,
+        handler: H,
+    ) -> Result<&mut Self, RouteError>
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.try_handle(path, Method::DELETE, handler)
+    }
+
+    /// Enables automatic redirection if the current route can't be matched but a
+    /// handler for the path with (without) the trailing slash exists.
+    /// For example if `/foo/` is requested but a route only exists for `/foo`, the
+    /// client is redirected to `/foo` with HTTP status code 301 for `GET` and `HEAD`
+    /// requests and 308 for all other request methods, see [`Router::redirect_policy`].
+    pub fn redirect_trailing_slash(mut self) -> Self {
+        self.redirect_trailing_slash = true;
+        self
+    }
+
+    /// If enabled, the router tries to fix the current request path, if no
+    /// handle is registered for it.
+    /// First superfluous path elements like `../` or `//` are removed.
+    /// Afterwards the router does a case-insensitive lookup of the cleaned path.
+    /// If a handle can be found for this route, the router makes a redirection
+    /// to the corrected path with status code 301 for `GET` and `HEAD` requests and 308 for
+    /// all other request methods.
+    /// For example `/FOO` and `/..//Foo` could be redirected to `/foo`.
+    /// `redirect_trailing_slash` is independent of this option.
+    /// The status and `Location` of the redirect can be configured with
+    /// [`Router::redirect_policy`].
+    pub fn redirect_fixed_path(mut self) -> Self {
+        self.redirect_fixed_path = true;
+        self
+    }
+
+    /// Configure the status and `Location` of the redirects enabled by
+    /// `redirect_trailing_slash` and `redirect_fixed_path`, see [`RedirectPolicy`].
+    /// The policy also applies to the redirects of directories served by
+    /// [`Router::serve_files`].
+    pub fn redirect_policy(mut self, policy: RedirectPolicy) -> Self {
+        self.redirect_policy = Arc::new(policy);
+        self
+    }
+
+    /// If enabled, the router checks if another method is allowed for the
+    /// current route, if the current request can not be routed.
+    /// If this is the case, the request is answered with `MethodNotAllowed`
+    /// and HTTP status code 405.
+    /// If no other Method is allowed, the request is delegated to the `NotFound`
+    /// handler.
+    pub fn handle_method_not_allowed(mut self) -> Self {
+        self.handle_method_not_allowed = true;
+        self
+    }
+
+    /// If enabled, routes are matched against the path of the request as it was sent,
+    /// instead of its percent-decoded form, so that an encoded `/` (`%2F`) in a
+    /// parameter does not split it into two segments. Parameter values are still
+    /// percent-decoded, see [`Params::get_raw`]. Static segments of routes must then
+    /// be registered in their encoded form, such as `/caf%C3%A9`.
+    /// ```rust
+    /// use httprouter::{Router, handler_fn};
+    /// use hyper::{Response, Body};
+    /// use std::convert::Infallible;
+    ///
+    /// // `GET /packages/%40scope%2Fname` matches with `name` set to `@scope/name`
+    /// let router = Router::default()
+    ///     .get("/packages/:name", handler_fn(|_| async {
+    ///         Ok::<_, Infallible>(Response::new(Body::empty()))
+    ///     }))
+    ///     .use_raw_path();
+    /// ```
+    pub fn use_raw_path(mut self) -> Self {
+        self.use_raw_path = true;
+        self
+    }
+
+    /// If enabled, requests whose parameters don't satisfy the constraints of the
+    /// matched route are answered with `400 Bad Request`, instead of being handled as
+    /// if the route didn't match.
+    /// ```rust
+    /// use httprouter::{Router, handler_fn};
+    /// use hyper::{Response, Body};
+    /// use std::convert::Infallible;
+    ///
+    /// let router = Router::default()
+    ///     .get("/orders/:id<u64>", handler_fn(|_| async {
+    ///         Ok::<_, Infallible>(Response::new(Body::empty()))
+    ///     }))
+    ///     .bad_request_on_constraint_failure();
+    /// ```
+    pub fn bad_request_on_constraint_failure(mut self) -> Self {
+        self.bad_request_on_constraint_failure = true;
+        self
+    }
+
+    /// If enabled, the router automatically replies to `OPTIONS` requests.
+    /// Custom `OPTIONS` handlers take priority over automatic replies.
+    pub fn handle_options(mut self) -> Self {
+        self.handle_options = true;
+        self
+    }
+
+    /// Whether `HEAD` requests to paths without a `HEAD` route are handled by the `GET`
+    /// route for the path, if any. The body of the response is discarded, but its
+    /// headers, including `Content-Length`, are kept, and `HEAD` is included in the
+    /// methods allowed for the path. Enabled by default.
+    /// ```rust
+    /// use httprouter::{Router, handler_fn};
+    /// use hyper::{Response, Body};
+    /// use std::convert::Infallible;
+    ///
+    /// let router = Router::default()
+    ///     .get("/", handler_fn(|_| async {
+    ///         Ok::<_, Infallible>(Response::new(Body::from("Hello, World!")))
+    ///     }))
+    ///     .auto_head(false);
+    /// ```
+    pub fn auto_head(mut self, enabled: bool) -> Self {
+        self.auto_head = enabled;
+        self
+    }
+
+    /// An optional handler that is called on automatic `OPTIONS` requests.
+    /// The handler is only called if `handle_options` is true and no `OPTIONS`
+    /// handler for the specific path was set.
+    /// The `Allowed` header is set before calling the handler.
+    pub fn global_options<H, F, E>(mut self, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.global_options = Some(self.fallback(HandlerServiceImpl::new(handler)));
+        self
+    }
+
+    /// Configurable handler which is called when no matching route is
+    /// found. By default, such requests are answered with `404 Not Found`, see
+    /// [`Router::responder`].
+    pub fn not_found<H, F, E>(mut self, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.not_found = Some(self.fallback(HandlerServiceImpl::new(handler)));
+        self
+    }
+
+    /// A configurable handler which is called when a request
+    /// cannot be routed and `handle_method_not_allowed` is true.
+    /// The `Allow` header with allowed request methods is set before the handler
+    /// is called.
+    pub fn method_not_allowed<H, F, E>(mut self, handler: H) -> Self
+    where
+        H: HandlerService<F, E>,
+        F: HandlerFuture<E>,
+        E: HandlerError,
+    {
+        self.method_not_allowed = Some(self.fallback(HandlerServiceImpl::new(handler)));
+        self
+    }
+
+    /// Wrap every route, and the `NotFound`, `MethodNotAllowed`, global `OPTIONS` and
+    /// [`Router::spa_fallback`] handlers, with a [`tower::Layer`], such as a timeout,
+    /// tracing or authentication middleware. Use [`Router::route_layer`] to only wrap
+    /// the routes.
+    ///
+    /// Layers apply to every route, including routes registered after the layer
+    /// is added or through a [`RouterHandle`], and each layer wraps the layers added
+    /// before it. Automatic responses, such as redirects and the default
+    /// `405 Method Not Allowed` response, are not passed through layers.
+    ///
+    /// The handlers are passed to the layer as a [`Handler`](crate::Handler), and the
+    /// wrapped service may return any error that can be boxed, as `tower` middleware
+    /// typically does.
+    /// ```rust
+    /// use httprouter::{Router, handler_fn};
+    /// use hyper::{Response, Body};
+    /// use std::convert::Infallible;
+    /// use std::time::Duration;
+    /// use tower::timeout::TimeoutLayer;
+    ///
+    /// let router = Router::default()
+    ///     .get("/", handler_fn(|_| async {
+    ///         Ok::<_, Infallible>(Response::new(Body::from("Hello, World!")))
+    ///     }))
+    ///     .layer(TimeoutLayer::new(Duration::from_secs(30)));
+    /// ```
+    ///
+    /// [`tower::Layer`]: https://docs.rs/tower/latest/tower/trait.Layer.html
+    pub fn layer<L>(mut self, layer: L) -> Self
+    where
+        L: Layer<Handler> + Send + Sync + 'static,
+        L::Service:
+            Service<Request<Body>, Response = Response<Body>> + Clone + Send + Sync + 'static,
+        <L::Service as Service<Request<Body>>>::Future: Send + Sync + 'static,
+        <L::Service as Service<Request<Body>>>::Error: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        let layer = BoxLayer::new(layer);
+        for handler in [
+            &mut self.global_options,
+            &mut self.not_found,
+            &mut self.method_not_allowed,
+            &mut self.spa_fallback,
+        ] {
+            if let Some(service) = handler.take() {
+                *handler = Some(layer.apply(service));
+            }
+        }
+
+        self.layers.push((layer, true));
+        self.relayer()
+    }
+
+    /// Wrap every route with a [`tower::Layer`], but not the fallback handlers. Unlike
+    /// with [`Router::layer`], requests that don't match a route are never passed to
+    /// the layer, which makes it the right choice for middleware such as
+    /// authentication, which should not turn a `404 Not Found` into a
+    /// `401 Unauthorized`.
+    ///
+    /// [`tower::Layer`]: https://docs.rs/tower/latest/tower/trait.Layer.html
+    pub fn route_layer<L>(mut self, layer: L) -> Self
+    where
+        L: Layer<Handler> + Send + Sync + 'static,
+        L::Service:
+            Service<Request<Body>, Response = Response<Body>> + Clone + Send + Sync + 'static,
+        <L::Service as Service<Request<Body>>>::Future: Send + Sync + 'static,
+        <L::Service as Service<Request<Body>>>::Error: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        self.layers.push((BoxLayer::new(layer), false));
+        self.relayer()
+    }
+
+    /// Recover from panics in handlers, including the fallback handlers, by answering
+    /// with the response built by the given function from the panic payload and the
+    /// metadata of the request. Panics that occur when calling a handler and when
+    /// polling the future it returns are both caught.
+    ///
+    /// Without a panic handler, a panicking handler unwinds through the server, which
+    /// typically closes the connection without a response. Note that the metadata of
+    /// every request is copied once a panic handler is set.
+    /// ```rust
+    /// use httprouter::Router;
+    /// use hyper::{Response, Body, StatusCode};
+    ///
+    /// let router = Router::default().panic_handler(|payload, parts| {
+    ///     let message = payload
+    ///         .downcast_ref::<&str>()
+    ///         .copied()
+    ///         .unwrap_or("unknown panic");
+    ///     eprintln!("{} {} panicked: {}", parts.method(), parts.uri(), message);
+    ///
+    ///     Response::builder()
+    ///         .status(StatusCode::INTERNAL_SERVER_ERROR)
+    ///         .body(Body::empty())
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn panic_handler<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(Box<dyn Any + Send>, RequestParts) -> Response<Body> + Send + Sync + 'static,
+    {
+        self.panic_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Answer requests whose handler, or any middleware wrapping it, returns an error
+    /// with the response built by the given function from the error and the metadata
+    /// of the request. The error can be downcast to the error type of the handler with
+    /// [`BoxError::downcast_ref`].
+    ///
+    /// Without an error handler, errors are returned to the server, which typically
+    /// closes the connection without a response. Errors from parsing parameters, such
+    /// as [`ParamsError`], are still answered with `400 Bad Request`. Note that the
+    /// metadata of every request is copied once an error handler is set.
+    /// ```rust
+    /// use httprouter::Router;
+    /// use hyper::{Response, Body, StatusCode};
+    /// use std::fmt;
+    ///
+    /// #[derive(Debug)]
+    /// enum AppError {
+    ///     Unauthorized,
+    /// }
+    ///
+    /// impl fmt::Display for AppError {
+    ///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    ///         f.write_str("unauthorized")
+    ///     }
+    /// }
+    ///
+    /// impl std::error::Error for AppError {}
+    ///
+    /// let router = Router::default().error_handler(|err, parts| {
+    ///     let status = match err.downcast_ref::<AppError>() {
+    ///         Some(AppError::Unauthorized) => StatusCode::UNAUTHORIZED,
+    ///         None => {
+    ///             eprintln!("{} {} failed: {}", parts.method(), parts.uri(), err);
+    ///             StatusCode::INTERNAL_SERVER_ERROR
+    ///         }
+    ///     };
+    ///
+    ///     Response::builder()
+    ///         .status(status)
+    ///         .body(Body::empty())
+    ///         .unwrap()
+    /// });
+    /// ```
+    pub fn error_handler<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(BoxError, RequestParts) -> Response<Body> + Send + Sync + 'static,
+    {
+        self.error_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Build the responses that the router generates itself with the given function:
+    /// `404 Not Found` and `405 Method Not Allowed` responses when no `NotFound` or
+    /// `MethodNotAllowed` handler is set, automatic responses to `OPTIONS` requests,
+    /// and redirects. By default, these responses have an empty body, as built by
+    /// [`BuiltinResponse::into_response`].
+    /// ```rust
+    /// use httprouter::{BuiltinResponse, Router};
+    /// use hyper::header::{self, HeaderValue};
+    ///
+    /// let router = Router::default().responder(|res, _| {
+    ///     let cache = matches!(res, BuiltinResponse::Redirect { .. });
+    ///     let mut res = res.into_response();
+    ///     if cache {
+    ///         let value = HeaderValue::from_static("max-age=3600");
+    ///         res.headers_mut().insert(header::CACHE_CONTROL, value);
+    ///     }
+    ///     res
+    /// });
+    /// ```
+    pub fn responder<F>(mut self, responder: F) -> Self
+    where
+        F: Fn(BuiltinResponse, RequestParts) -> Response<Body> + Send + Sync + 'static,
+    {
+        self.responder = Some(Arc::new(responder));
+        self
+    }
+
+    /// Describe the responses that the router generates itself in their body, with
+    /// an HTML page for clients that accept `text/html`, and with an [RFC 7807]
+    /// `application/problem+json` object otherwise. See
+    /// [`BuiltinResponse::into_problem`].
+    ///
+    /// [RFC 7807]: https://www.rfc-editor.org/rfc/rfc7807
+    pub fn problem_details(self) -> Self {
+        self.responder(|res, parts| res.into_problem(&parts))
+    }
+
+    /// Wraps a fallback handler with the layers that apply to fallback handlers.
+    fn fallback<S>(&self, service: S) -> Box<dyn StoredService>
+    where
+        S: StoredService + Clone,
+    {
+        self.layers
+            .iter()
+            .filter(|(_, fallbacks)| *fallbacks)
+            .fold(Box::new(service), |service, (layer, _)| {
+                layer.apply(service)
+            })
+    }
+
+    /// Rebuilds the routing tree, applying the current layers to every route.
+    fn relayer(self) -> Self {
+        self.rebuild(self.routes.clone())
+            .expect("registered routes are valid")
+    }
+
+    /// Returns a list of the allowed methods for a specific path
+    /// ```rust
+    /// use httprouter::{Router, handler_fn};
+    /// use hyper::{Response, Body, Method};
+    /// use std::convert::Infallible;
+    ///
+    /// let router = Router::default()
+    ///     .get("/home", handler_fn(|_| async {
+    ///         Ok::<_, Infallible>(Response::new(Body::from("Welcome!")))
+    ///     }))
+    ///     .post("/home", handler_fn(|_| async {
+    ///         Ok::<_, Infallible>(Response::new(Body::from("{{ message: \"Welcome!\" }}")))
+    ///     }));
+    ///
+    /// let allowed = router.allowed("/home");
+    /// assert!(allowed.contains(&"GET"));
+    /// assert!(allowed.contains(&"POST"));
+    /// assert!(allowed.contains(&"OPTIONS"));
+    /// // `HEAD` requests are handled by the `GET` route
+    /// assert!(allowed.contains(&"HEAD"));
+    /// # assert_eq!(allowed.len(), 4);
+    /// ```
+    pub fn allowed(&self, path: impl Into<String>) -> Vec<&str> {
+        let path = path.into();
+        let (mut allowed, any) = match path.as_ref() {
+            "*" => (
+                self.trees.keys().map(AsRef::as_ref).collect::<Vec<_>>(),
+                self.routes.iter().any(|route| route.method.is_none()),
+            ),
+            _ => (
+                self.trees
+                    .iter()
+                    .filter(|(_, node)| matches(node, &path))
+                    .map(|(method, _)| method.as_ref())
+                    .collect::<Vec<_>>(),
+                matches(&self.any, &path),
+            ),
+        };
+
+        if any {
+            allowed.extend(ANY_METHODS.iter().map(AsRef::as_ref));
+        }
+
+        if self.auto_head && allowed.contains(&Method::GET.as_str()) {
+            allowed.push(Method::HEAD.as_ref());
+        }
+
+        allowed.retain(|&method| method != Method::OPTIONS);
+        allowed.sort_unstable();
+        allowed.dedup();
+
+        if !allowed.is_empty() {
+            allowed.push(Method::OPTIONS.as_ref())
+        }
+
+        allowed
+    }
+}
+
+/// Returns the catch-all path that a router or service nested under the prefix is
+/// registered at.
+pub(crate) fn nested_path(prefix: &str) -> String {
+    join(prefix, &["/*", NESTED_PATH].concat())
+}
+
+/// Returns `true` if the path matches a route in the tree whose constraints are satisfied.
+fn matches(node: &Node<Endpoint>, path: &str) -> bool {
+    match node.at(path) {
+        Ok(lookup) => {
+            let params = lookup
+                .params
+                .iter()
+                .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                .collect::<Vec<_>>();
+            lookup.value.reject(&params).is_none()
+        }
+        Err(_) => false,
+    }
+}
+
+impl Default for Router {
+    fn default() -> Self {
+        Self {
+            trees: HashMap::new(),
+            any: Node::default(),
+            routes: Vec::new(),
+            mounted: HashMap::new(),
+            names: HashMap::new(),
+            redirect_trailing_slash: true,
+            redirect_fixed_path: true,
+            handle_method_not_allowed: true,
+            handle_options: true,
+            auto_head: true,
+            use_raw_path: false,
+            bad_request_on_constraint_failure: false,
+            redirect_policy: Arc::new(RedirectPolicy::default()),
+            global_options: None,
             method_not_allowed: None,
-            not_found: Some(Box::new(HandlerServiceImpl::new(handler_fn(|_| async {
-                Ok::<_, hyper::Error>(Response::builder().status(400).body(Body::empty()).unwrap())
-            })))),
+            spa_fallback: None,
+            layers: Vec::new(),
+            panic_handler: None,
+            error_handler: None,
+            responder: None,
+            not_found: None,
+        }
+    }
+}
+
+impl Router {
+    /// Returns the routes registered on the router, in registration order.
+    /// Removes the `NotFound` handler, see [`RouterHandle::remove_not_found`].
+    pub(crate) fn without_not_found(mut self) -> Self {
+        self.not_found = None;
+        self
+    }
+
+    /// Removes the `MethodNotAllowed` handler, see
+    /// [`RouterHandle::remove_method_not_allowed`].
+    pub(crate) fn without_method_not_allowed(mut self) -> Self {
+        self.method_not_allowed = None;
+        self
+    }
+
+    pub(crate) fn entries(&self) -> &[RouteEntry] {
+        &self.routes
+    }
+
+    /// Builds a router with the same configuration as this one, but with the given routes.
+    pub(crate) fn rebuild(&self, routes: Vec<RouteEntry>) -> Result<Router, RouteError> {
+        let mut router = Router {
+            trees: HashMap::new(),
+            any: Node::default(),
+            routes: Vec::with_capacity(routes.len()),
+            mounted: HashMap::new(),
+            names: HashMap::new(),
+            redirect_trailing_slash: self.redirect_trailing_slash,
+            redirect_fixed_path: self.redirect_fixed_path,
+            handle_method_not_allowed: self.handle_method_not_allowed,
+            handle_options: self.handle_options,
+            auto_head: self.auto_head,
+            use_raw_path: self.use_raw_path,
+            bad_request_on_constraint_failure: self.bad_request_on_constraint_failure,
+            redirect_policy: self.redirect_policy.clone(),
+            global_options: self.global_options.clone(),
+            method_not_allowed: self.method_not_allowed.clone(),
+            not_found: self.not_found.clone(),
+            spa_fallback: self.spa_fallback.clone(),
+            layers: self.layers.clone(),
+            panic_handler: self.panic_handler.clone(),
+            error_handler: self.error_handler.clone(),
+            responder: self.responder.clone(),
+        };
+
+        for route in routes {
+            router.insert(route)?;
         }
+
+        // the names of nested routers that are still mounted
+        for (path, mount) in self.mounted.iter() {
+            if router.routes.iter().any(|route| route.path == *path) {
+                router.check_names(mount)?;
+                router.names.extend(mount.names.iter().cloned());
+                router.mounted.insert(path.clone(), mount.clone());
+            }
+        }
+
+        Ok(router)
     }
 }
 
@@ -562,11 +2014,11 @@ impl<T> Service<T> for MakeRouterService {
 
 #[doc(hidden)]
 #[derive(Clone)]
-pub struct RouterService(Arc<Router>);
+pub struct RouterService(Arc<ArcSwap<Router>>);
 
 impl RouterService {
     fn new(router: Router) -> Self {
-        RouterService(Arc::new(router))
+        RouterService(Arc::new(ArcSwap::from_pointee(router)))
     }
 }
 
@@ -580,7 +2032,7 @@ impl Service<Request<Body>> for RouterService {
     }
 
     fn call(&mut self, req: Request<Body>) -> Self::Future {
-        self.0.clone().serve(req)
+        self.0.load().serve(req)
     }
 }
 
@@ -609,6 +2061,35 @@ impl Router {
         MakeRouterService(RouterService::new(self))
     }
 
+    /// Converts the `Router` into a `Service`, along with a [`RouterHandle`] that can be
+    /// used to modify its routes while it is being served.
+    /// ```rust,no_run
+    /// # use httprouter::{Router, handler_fn};
+    /// # use hyper::{Response, Body, Method};
+    /// # use std::convert::Infallible;
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let handler = handler_fn(|_| async {
+    ///     Ok::<_, Infallible>(Response::new(Body::empty()))
+    /// });
+    ///
+    /// let router = Router::default().get("/", handler.clone());
+    /// let (service, handle) = router.into_service_with_handle();
+    ///
+    /// // add a route while the server is running
+    /// handle.add("/tenants/acme", Method::GET, handler)?;
+    ///
+    /// hyper::Server::bind(&([127, 0, 0, 1], 3030).into())
+    ///     .serve(service)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn into_service_with_handle(self) -> (MakeRouterService, RouterHandle) {
+        let service = RouterService::new(self);
+        let handle = RouterHandle::new(service.0.clone());
+        (MakeRouterService(service), handle)
+    }
+
     /// An asynchronous function from a `Request` to a `Response`. You will generally not need to use
     /// this function directly, and instead use
     /// [`Router::into_service`](crate::Router::into_service). However, it may be useful when
@@ -639,46 +2120,143 @@ impl Router {
     /// # }
     /// ```
     pub fn serve(&self, mut req: Request<Body>) -> ResponseFut {
-        let root = self.trees.get(req.method());
-        let path = req.uri().path();
-        if let Some(root) = root {
+        let raw_path = req.uri().path();
+        let decoded = if self.use_raw_path {
+            None
+        } else {
+            DecodedPath::new(raw_path)
+        };
+        let path = decoded.as_ref().map_or(raw_path, DecodedPath::as_str);
+        let get = match *req.method() {
+            Method::HEAD if self.auto_head => self.trees.get(&Method::GET),
+            _ => None,
+        };
+        // routes registered for the request method take precedence over routes for any method,
+        // which take precedence over `GET` routes for `HEAD` requests
+        let trees = || {
+            self.trees
+                .get(req.method())
+                .into_iter()
+                .chain(Some(&self.any))
+                .chain(get)
+        };
+
+        // automatic `OPTIONS` responses take precedence over routes for any method,
+        // but not over nested routers and services, which answer them themselves
+        let skip_any = req.method() == Method::OPTIONS && self.handle_options;
+
+        let mut tsr = false;
+        let mut rejected = None;
+        for root in trees() {
             match root.at(path) {
+                Ok(lookup)
+                    if skip_any
+                        && std::ptr::eq(root, &self.any)
+                        && !lookup.params.iter().any(|(key, _)| key == NESTED_PATH) => {}
                 Ok(lookup) => {
-                    let mut value = lookup.value.clone();
-                    let vec = lookup
-                        .params
-                        .iter()
-                        .map(|(key, value)| (key.to_owned(), value.to_owned()))
-                        .collect();
-                    req.extensions_mut().insert(Params { vec });
-                    return ResponseFutKind::Boxed(value.call(req)).into();
-                }
-                Err(err) => {
-                    if req.method() != Method::CONNECT && path != "/" {
-                        let code = match *req.method() {
-                            // Moved Permanently, request with GET method
-                            Method::GET => StatusCode::MOVED_PERMANENTLY,
-                            // Permanent Redirect, request with same method
-                            _ => StatusCode::PERMANENT_REDIRECT,
+                    let mut vec = Vec::new();
+                    let mut raw = Vec::new();
+                    for (key, value) in lookup.params.iter() {
+                        let (value, encoded) = match decoded {
+                            Some(ref decoded) => {
+                                (value.to_owned(), decoded.encoded(raw_path, value))
+                            }
+                            None => (decode(value).into_owned(), Some(value)),
                         };
 
-                        if err.tsr() && self.redirect_trailing_slash {
-                            let path = if path.len() > 1 && path.ends_with('/') {
-                                path[..path.len() - 1].to_owned()
-                            } else {
-                                [path, "/"].join("")
-                            };
-
-                            return ResponseFutKind::Redirect(path, code).into();
-                        }
-
-                        if self.redirect_fixed_path {
-                            if let Some(fixed_path) =
-                                root.path_ignore_case(clean(path), self.redirect_trailing_slash)
-                            {
-                                return ResponseFutKind::Redirect(fixed_path, code).into();
-                            }
-                        }
+                        vec.push((key.to_owned(), value));
+                        raw.push(encoded.map(str::to_owned));
+                    }
+
+                    if let Some(param) = lookup.value.reject(&vec) {
+                        rejected = rejected.or_else(|| Some(param.clone()));
+                        continue;
+                    }
+
+                    let handler = lookup.value.service.clone();
+                    let head = matches!(get, Some(get) if std::ptr::eq(root, get));
+
+                    // merge with any parameters captured by a parent router
+                    let mut params = req.extensions_mut().remove::<Params>().unwrap_or_default();
+                    for ((key, value), raw) in vec.into_iter().zip(raw) {
+                        params.push_encoded(key, value, raw);
+                    }
+                    req.extensions_mut().insert(params);
+
+                    // directories served by `serve_files` are redirected like routes
+                    req.extensions_mut().insert(RouterRedirects {
+                        trailing_slash: self.redirect_trailing_slash,
+                        policy: self.redirect_policy.clone(),
+                    });
+
+                    let res = self.call(handler, req);
+                    if head {
+                        let res = async move { res.await.map(strip_body) };
+                        return ResponseFutKind::Boxed(Box::pin(res)).into();
+                    }
+
+                    return res;
+                }
+                Err(err) => tsr |= err.tsr(),
+            }
+        }
+
+        // otherwise, the request is handled as if the route didn't match
+        if self.bad_request_on_constraint_failure {
+            if let Some((key, value)) = rejected {
+                let err = ParamsError::Invalid {
+                    key,
+                    value,
+                    message: "value does not satisfy the route constraint".into(),
+                };
+                return ResponseFutKind::Ready(Some(err.into())).into();
+            }
+        }
+
+        if req.method() != Method::CONNECT && path != "/" {
+            let policy = &self.redirect_policy;
+
+            if tsr && self.redirect_trailing_slash {
+                if let Some(status) = policy.status(Redirect::TrailingSlash, req.method()) {
+                    let path = if path.len() > 1 && path.ends_with('/') {
+                        path[..path.len() - 1].to_owned()
+                    } else {
+                        [path, "/"].concat()
+                    };
+
+                    // the path was decoded for matching
+                    let path = match decoded {
+                        Some(_) => encode_path(&path),
+                        None => path,
+                    };
+
+                    return self.redirect(&req, &path, status);
+                }
+            }
+
+            if self.redirect_fixed_path {
+                if let Some(status) = policy.status(Redirect::FixedPath, req.method()) {
+                    // encoded dot segments are removed as well
+                    let cleaned = if self.use_raw_path {
+                        clean(&normalize_encoding(path))
+                    } else {
+                        clean(path)
+                    };
+                    // the fixed path must satisfy the constraints of its route, and differ
+                    // from a path that was not handled, such as an `OPTIONS` request for
+                    // a route for any method
+                    let fixed_path = trees().find_map(|root| {
+                        root.path_ignore_case(&cleaned, self.redirect_trailing_slash)
+                            .filter(|fixed_path| fixed_path != path && matches(root, fixed_path))
+                    });
+
+                    if let Some(fixed_path) = fixed_path {
+                        // the path was decoded for matching
+                        let fixed_path = match decoded {
+                            Some(_) => encode_path(&fixed_path),
+                            None => fixed_path,
+                        };
+                        return self.redirect(&req, &fixed_path, status);
                     }
                 }
             }
@@ -689,8 +2267,11 @@ impl Router {
 
             if !allow.is_empty() {
                 return match self.global_options {
-                    Some(ref handler) => ResponseFutKind::Boxed(handler.clone().call(req)).into(),
-                    None => ResponseFutKind::Options(allow.join(", ")).into(),
+                    Some(ref handler) => self.call(handler.clone(), req),
+                    None => {
+                        let allowed = allow.into_iter().map(str::to_owned).collect();
+                        self.respond(BuiltinResponse::Options { allowed }, &req)
+                    }
                 };
             }
         } else if self.handle_method_not_allowed {
@@ -698,17 +2279,95 @@ impl Router {
 
             if !allow.is_empty() {
                 return match self.method_not_allowed {
-                    Some(ref handler) => ResponseFutKind::Boxed(handler.clone().call(req)).into(),
-                    None => ResponseFutKind::MethodNotAllowed(allow.join(", ")).into(),
+                    Some(ref handler) => self.call(handler.clone(), req),
+                    None => {
+                        let allowed = allow.into_iter().map(str::to_owned).collect();
+                        self.respond(BuiltinResponse::MethodNotAllowed { allowed }, &req)
+                    }
                 };
             }
         }
 
+        if let Some(ref fallback) = self.spa_fallback {
+            let method = req.method();
+            if (method == Method::GET || method == Method::HEAD) && accepts_html(req.headers()) {
+                return self.call(fallback.clone(), req);
+            }
+        }
+
+        self.serve_not_found(req)
+    }
+
+    fn serve_not_found(&self, req: Request<Body>) -> ResponseFut {
         match self.not_found {
-            Some(ref handler) => ResponseFutKind::Boxed(handler.clone().call(req)).into(),
-            None => ResponseFutKind::NotFound.into(),
+            Some(ref handler) => self.call(handler.clone(), req),
+            None => self.respond(BuiltinResponse::NotFound, &req),
+        }
+    }
+
+    /// Redirects the request to the given path.
+    fn redirect(&self, req: &Request<Body>, path: &str, status: StatusCode) -> ResponseFut {
+        let location = self.redirect_policy.location(req, path);
+        self.respond(BuiltinResponse::Redirect { location, status }, req)
+    }
+
+    /// Responds with the response of the router, built by the responder if one is set.
+    fn respond(&self, res: BuiltinResponse, req: &Request<Body>) -> ResponseFut {
+        let res = match self.responder {
+            Some(ref responder) => responder(res, RequestParts::new(req)),
+            None => res.into_response(),
+        };
+
+        ResponseFutKind::Ready(Some(res)).into()
+    }
+
+    /// Calls the handler, catching any panics and errors if a panic or error handler
+    /// is set.
+    fn call(&self, mut handler: Box<dyn StoredService>, req: Request<Body>) -> ResponseFut {
+        if self.panic_handler.is_none() && self.error_handler.is_none() {
+            return ResponseFutKind::Boxed(handler.call(req)).into();
+        }
+
+        let parts = RequestParts::new(&req);
+        let future: Pin<Box<dyn Future<Output = _> + Send + Sync>> = match self.panic_handler {
+            Some(ref panic_handler) => Box::pin(CatchPanic::new(
+                handler,
+                req,
+                parts.clone(),
+                panic_handler.clone(),
+            )),
+            None => handler.call(req),
+        };
+
+        match self.error_handler {
+            Some(ref error_handler) => {
+                let error_handler = error_handler.clone();
+                ResponseFutKind::Boxed(Box::pin(async move {
+                    match future.await {
+                        // answered with `400 Bad Request` below
+                        Err(err) if err.is::<ParamsError>() => Err(err),
+                        Err(err) => Ok(error_handler(err, parts)),
+                        res => res,
+                    }
+                }))
+                .into()
+            }
+            None => ResponseFutKind::Boxed(future).into(),
+        }
+    }
+}
+
+/// Discards the body of a response to a `HEAD` request that was handled by a `GET` route,
+/// keeping its length.
+fn strip_body(mut res: Response<Body>) -> Response<Body> {
+    if !res.headers().contains_key(header::CONTENT_LENGTH) {
+        if let Some(len) = res.body().size_hint().exact() {
+            res.headers_mut().insert(header::CONTENT_LENGTH, len.into());
         }
     }
+
+    *res.body_mut() = Body::empty();
+    res
 }
 
 pub struct ResponseFut {
@@ -721,12 +2380,9 @@ impl From<ResponseFutKind> for ResponseFut {
     }
 }
 
-enum ResponseFutKind {
+pub(crate) enum ResponseFutKind {
     Boxed(Pin<Box<dyn Future<Output = Result<Response<Body>, BoxError>> + Send + Sync>>),
-    Redirect(String, StatusCode),
-    MethodNotAllowed(String),
-    Options(String),
-    NotFound,
+    Ready(Option<Response<Body>>),
 }
 
 impl Future for ResponseFut {
@@ -734,33 +2390,57 @@ impl Future for ResponseFut {
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let ready = match self.kind {
-            ResponseFutKind::Boxed(ref mut fut) => ready!(fut.as_mut().poll(cx)),
-            ResponseFutKind::Redirect(ref path, code) => Ok(Response::builder()
-                .header(header::LOCATION, path.as_str())
-                .status(code)
-                .body(Body::empty())
-                .unwrap()),
-            ResponseFutKind::NotFound => Ok(Response::builder()
-                .status(StatusCode::NOT_FOUND)
-                .body(Body::empty())
-                .unwrap()),
-            ResponseFutKind::Options(ref allowed) => Ok(Response::builder()
-                .header(header::ALLOW, allowed)
-                .body(Body::empty())
-                .unwrap()),
-            ResponseFutKind::MethodNotAllowed(ref allowed) => Ok(Response::builder()
-                .header(header::ALLOW, allowed)
-                .status(StatusCode::METHOD_NOT_ALLOWED)
-                .body(Body::empty())
-                .unwrap()),
+            ResponseFutKind::Boxed(ref mut fut) => match ready!(fut.as_mut().poll(cx)) {
+                Err(BoxError(err)) => match err.downcast::<ParamsError>() {
+                    Ok(err) => Ok((*err).into()),
+                    Err(err) => Err(BoxError(err)),
+                },
+                res => res,
+            },
+            ResponseFutKind::Ready(ref mut res) => Ok(res.take().expect("polled after completion")),
         };
 
         Poll::Ready(ready)
     }
 }
 
+/// An error returned by a handler or by middleware wrapping it.
 pub struct BoxError(Box<dyn StdError + Send + Sync>);
 
+impl BoxError {
+    fn new<E: HandlerError>(err: E) -> Self {
+        Self::from_boxed(Box::new(err))
+    }
+
+    pub(crate) fn from_boxed(err: Box<dyn StdError + Send + Sync>) -> Self {
+        // avoid double boxing errors from nested routers
+        match err.downcast::<BoxError>() {
+            Ok(err) => *err,
+            Err(err) => BoxError(err),
+        }
+    }
+
+    /// Returns `true` if the error is of type `E`.
+    pub fn is<E: StdError + 'static>(&self) -> bool {
+        self.0.is::<E>()
+    }
+
+    /// Returns a reference to the error if it is of type `E`.
+    pub fn downcast_ref<E: StdError + 'static>(&self) -> Option<&E> {
+        self.0.downcast_ref()
+    }
+
+    /// Returns the error if it is of type `E`, or `self` otherwise.
+    pub fn downcast<E: StdError + 'static>(self) -> Result<E, Self> {
+        self.0.downcast().map(|err| *err).map_err(BoxError)
+    }
+
+    /// Returns the boxed error.
+    pub fn into_inner(self) -> Box<dyn StdError + Send + Sync> {
+        self.0
+    }
+}
+
 impl fmt::Display for BoxError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> std::fmt::Result {
         std::fmt::Display::fmt(&self.0, f)
@@ -778,3 +2458,636 @@ impl StdError for BoxError {
         Some(&*self.0)
     }
 }
+
+/// The routes and names of a router nested with [`Router::nest`], with the prefix joined.
+#[derive(Clone, Debug)]
+struct Mount {
+    routes: Vec<Route>,
+    names: Vec<(String, String)>,
+}
+
+/// A route registered on a [`Router`], as returned by [`Router::routes`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Route {
+    method: Option<Method>,
+    path: String,
+    name: Option<String>,
+    mount: bool,
+}
+
+impl Route {
+    /// The method of the route, or `None` if the route matches any method.
+    pub fn method(&self) -> Option<&Method> {
+        self.method.as_ref()
+    }
+
+    /// The path pattern of the route, as it was registered.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The name of the route, if it was registered with one.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Whether the route is a service mounted with [`Router::nest_service`], in which
+    /// case its path ends with an unnamed catch-all, such as `/legacy/*`.
+    pub fn is_mount(&self) -> bool {
+        self.mount
+    }
+}
+
+impl fmt::Display for Route {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.method {
+            Some(ref method) => write!(f, "{} {}", method, self.path)?,
+            None => write!(f, "* {}", self.path)?,
+        }
+
+        match self.name {
+            Some(ref name) => write!(f, " ({})", name),
+            None => Ok(()),
+        }
+    }
+}
+
+/// An error that occurred while registering a route.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteError {
+    /// The path is not a valid route pattern.
+    InvalidPath { path: String, reason: String },
+    /// The path conflicts with a route previously registered for the same method.
+    Conflict { path: String, with: String },
+    /// A handler is already registered for the same path and method.
+    Duplicate { path: String, method: Method },
+    /// A catch-all parameter was used anywhere other than the end of the path.
+    InvalidCatchAll { path: String },
+    /// A route with the same name is already registered.
+    DuplicateName { name: String, path: String },
+}
+
+impl RouteError {
+    pub(crate) fn from_insert(path: String, err: InsertError) -> Self {
+        match err {
+            InsertError::Conflict { with } => RouteError::Conflict { path, with },
+            InsertError::InvalidCatchAll => RouteError::InvalidCatchAll { path },
+            err => RouteError::InvalidPath {
+                path,
+                reason: err.to_string(),
+            },
+        }
+    }
+
+    /// Returns the path of the route that failed to register.
+    pub fn path(&self) -> &str {
+        match self {
+            RouteError::InvalidPath { path, .. }
+            | RouteError::Conflict { path, .. }
+            | RouteError::Duplicate { path, .. }
+            | RouteError::InvalidCatchAll { path }
+            | RouteError::DuplicateName { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for RouteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RouteError::InvalidPath { path, reason } => {
+                write!(f, "invalid route '{}': {}", path, reason)
+            }
+            RouteError::Conflict { path, with } => write!(
+                f,
+                "route '{}' conflicts with previously registered route '{}'",
+                path, with
+            ),
+            RouteError::Duplicate { path, method } => write!(
+                f,
+                "a handler is already registered for {} '{}'",
+                method, path
+            ),
+            RouteError::InvalidCatchAll { path } => write!(
+                f,
+                "invalid route '{}': catch-all parameters are only allowed at the end of a route",
+                path
+            ),
+            RouteError::DuplicateName { name, path } => write!(
+                f,
+                "cannot register route '{}': a route named '{}' is already registered",
+                path, name
+            ),
+        }
+    }
+}
+
+impl StdError for RouteError {}
+
+/// An error that occurred while building a URL with [`Router::url_for`].
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UrlError {
+    /// No route is registered with the given name.
+    UnknownRoute { name: String },
+    /// A parameter of the route was not supplied.
+    MissingParam { name: String, param: String },
+}
+
+impl fmt::Display for UrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UrlError::UnknownRoute { name } => write!(f, "no route named '{}'", name),
+            UrlError::MissingParam { name, param } => {
+                write!(f, "missing parameter '{}' for route '{}'", param, name)
+            }
+        }
+    }
+}
+
+impl StdError for UrlError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    fn handler(
+    ) -> impl HandlerService<future::Ready<Result<Response<Body>, Infallible>>, Infallible> {
+        handler_fn(|_| future::ok::<_, Infallible>(Response::new(Body::empty())))
+    }
+
+    #[test]
+    fn try_handle_errors() {
+        let mut router = Router::default();
+        router.try_get("/users/:id", handler()).unwrap();
+        router.try_post("/users/:id", handler()).unwrap();
+
+        assert_eq!(
+            router.try_get("users", handler()).err(),
+            Some(RouteError::InvalidPath {
+                path: "users".into(),
+                reason: "path must begin with '/'".into()
+            })
+        );
+
+        assert_eq!(
+            router.try_get("/users/:id", handler()).err(),
+            Some(RouteError::Duplicate {
+                path: "/users/:id".into(),
+                method: Method::GET
+            })
+        );
+
+        assert!(matches!(
+            router.try_get("/users/:name", handler()),
+            Err(RouteError::Conflict { .. })
+        ));
+
+        assert_eq!(
+            router.try_get("/src/*filepath/x", handler()).err(),
+            Some(RouteError::InvalidCatchAll {
+                path: "/src/*filepath/x".into()
+            })
+        );
+
+        // routes for several methods are registered together, or not at all
+        router
+            .try_handle("/files/*filepath", Method::HEAD, handler())
+            .unwrap();
+        assert!(matches!(
+            router.try_serve_files("/files/*filepath", "."),
+            Err(RouteError::Duplicate { .. })
+        ));
+        assert!(matches!(
+            router.try_methods(&[Method::PUT, Method::POST], "/users/:id", handler()),
+            Err(RouteError::Duplicate { .. })
+        ));
+
+        assert_eq!(router.allowed("/files/a"), ["HEAD", "OPTIONS"]);
+        assert_eq!(
+            router.allowed("/users/1"),
+            ["GET", "HEAD", "POST", "OPTIONS"]
+        );
+    }
+
+    #[tokio::test]
+    async fn nest_strips_prefix() {
+        let echo = handler_fn(|req: Request<Body>| async move {
+            let params = req.extensions().get::<Params>().unwrap();
+            let body = format!(
+                "{} {} {}",
+                req.uri(),
+                params.get("tenant").unwrap(),
+                params.get("id").unwrap()
+            );
+            Ok::<_, Infallible>(Response::new(Body::from(body)))
+        });
+
+        let admin = Router::default().get("/users/:id", echo);
+        let router = Router::default().nest("/tenants/:tenant/admin", admin);
+
+        let req = Request::get("/tenants/acme/admin/users/7?page=2")
+            .body(Body::empty())
+            .unwrap();
+        let res = router.serve(req).await.unwrap();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "/users/7?page=2 acme 7");
+
+        let req = Request::get("/tenants/acme/admin/users/7/")
+            .body(Body::empty())
+            .unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(
+            res.headers()[header::LOCATION],
+            "/tenants/acme/admin/users/7"
+        );
+    }
+
+    #[tokio::test]
+    async fn params_error_is_bad_request() {
+        let router = Router::default().get(
+            "/orders/:id",
+            handler_fn(|req: Request<Body>| async move {
+                let params = req.extensions().get::<Params>().unwrap();
+                let id: u64 = params.parse("id")?;
+                Ok::<_, ParamsError>(Response::new(Body::from(id.to_string())))
+            }),
+        );
+
+        let req = Request::get("/orders/abc").body(Body::empty()).unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let req = Request::get("/orders/7").body(Body::empty()).unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn percent_encoded_paths() {
+        // responds with the decoded and raw values of the parameters
+        let echo = handler_fn(|req: Request<Body>| async move {
+            let params = req.extensions().get::<Params>().unwrap();
+            let body = params
+                .iter()
+                .map(|(key, value)| [value.as_str(), " ", params.get_raw(key).unwrap()].concat())
+                .collect::<Vec<_>>()
+                .join(",");
+            Ok::<_, Infallible>(Response::new(Body::from(body)))
+        });
+        let body = |res: Response<Body>| async move {
+            let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            String::from_utf8(body.to_vec()).unwrap()
+        };
+
+        let router = Router::default()
+            .get("/users/:name", echo.clone())
+            .get("/café", echo.clone())
+            .get("/files/*path", echo.clone());
+
+        for (path, expected) in [
+            ("/users/John%20Doe", "John Doe John%20Doe"),
+            ("/users/john", "john john"),
+            ("/caf%C3%A9", ""),
+            ("/files/a%2Fb/c%25", "/a/b/c% /a%2Fb/c%25"),
+        ]
+        .iter()
+        {
+            let req = Request::get(*path).body(Body::empty()).unwrap();
+            let res = router.serve(req).await.unwrap();
+            assert_eq!(res.status(), StatusCode::OK, "{}", path);
+            assert_eq!(body(res).await, *expected, "{}", path);
+        }
+
+        // an encoded slash splits the parameter
+        let req = Request::get("/users/a%2Fb").body(Body::empty()).unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        // redirects are encoded, unless the path was not decoded
+        for (path, location) in [("/USERS/%FF", "/users/%FF"), ("/USERS/a:b", "/users/a:b")].iter()
+        {
+            let req = Request::get(*path).body(Body::empty()).unwrap();
+            let res = router.serve(req).await.unwrap();
+            assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY, "{}", path);
+            assert_eq!(res.headers()[header::LOCATION], *location, "{}", path);
+        }
+
+        let req = Request::get("/CAF%C3%A9").body(Body::empty()).unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.headers()[header::LOCATION], "/caf%C3%A9");
+        let req = Request::get("/users/John%20Doe/")
+            .body(Body::empty())
+            .unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.headers()[header::LOCATION], "/users/John%20Doe");
+
+        let router = Router::default()
+            .get("/users/:name", echo.clone())
+            .get("/caf%C3%A9", echo)
+            .use_raw_path();
+
+        let req = Request::get("/users/a%2Fb").body(Body::empty()).unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(body(res).await, "a/b a%2Fb");
+
+        let req = Request::get("/caf%C3%A9").body(Body::empty()).unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // encoded dot segments are cleaned
+        let req = Request::get("/x/%2E%2E/CAF%c3%a9")
+            .body(Body::empty())
+            .unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.headers()[header::LOCATION], "/caf%C3%A9");
+    }
+
+    #[tokio::test]
+    async fn auto_head() {
+        let text = |body: &'static str| {
+            handler_fn(move |_| async move { Ok::<_, Infallible>(Response::new(Body::from(body))) })
+        };
+        let router = Router::default()
+            .get("/", text("Hello, World!"))
+            .get("/explicit", text("get"))
+            .head("/explicit", text("head"));
+
+        let req = Request::head("/").body(Body::empty()).unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CONTENT_LENGTH], "13");
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert!(body.is_empty());
+
+        // explicit `HEAD` routes take precedence
+        let req = Request::head("/explicit").body(Body::empty()).unwrap();
+        let res = router.serve(req).await.unwrap();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "head");
+
+        let req = Request::post("/").body(Body::empty()).unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.headers()[header::ALLOW], "GET, HEAD, OPTIONS");
+
+        let router = router.auto_head(false);
+        let req = Request::head("/").body(Body::empty()).unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()[header::ALLOW], "GET, OPTIONS");
+    }
+
+    #[tokio::test]
+    async fn constraints_checked_at_match_time() {
+        let router = Router::default()
+            .get("/orders/:id<u64>", handler())
+            .get("/files/:name<[a-z0-9_-]+>", handler())
+            .not_found(handler_fn(|_| async {
+                Ok::<_, Infallible>(
+                    Response::builder()
+                        .status(StatusCode::NOT_FOUND)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+            }));
+
+        let tests = [
+            ("/orders/42", StatusCode::OK),
+            ("/orders/abc", StatusCode::NOT_FOUND),
+            ("/files/read_me", StatusCode::OK),
+            ("/files/README", StatusCode::NOT_FOUND),
+        ];
+
+        for (path, status) in tests.iter() {
+            let req = Request::get(*path).body(Body::empty()).unwrap();
+            let res = router.serve(req).await.unwrap();
+            assert_eq!(res.status(), *status, "{}", path);
+        }
+
+        // other routes for the path are still considered
+        let router = Router::default()
+            .get("/orders/:id<u64>", handler())
+            .delete("/orders/:id", handler());
+
+        let req = Request::get("/orders/abc").body(Body::empty()).unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()[header::ALLOW], "DELETE, OPTIONS");
+
+        let req = Request::get("/ORDERS/42").body(Body::empty()).unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(res.headers()[header::LOCATION], "/orders/42");
+
+        let router = router.bad_request_on_constraint_failure();
+
+        let req = Request::get("/orders/abc").body(Body::empty()).unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        assert!(matches!(
+            Router::default().try_get("/orders/:id<[0-9>", handler()),
+            Err(RouteError::InvalidPath { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn any_and_custom_methods() {
+        let echo = |name: &'static str| {
+            handler_fn(move |_| async move { Ok::<_, Infallible>(Response::new(Body::from(name))) })
+        };
+
+        let propfind = Method::from_bytes(b"PROPFIND").unwrap();
+        let router = Router::default()
+            .any("/files/*path", echo("any"))
+            .get("/files/*path", echo("get"))
+            .methods(&[propfind.clone(), Method::PUT], "/dav", echo("dav"));
+
+        let tests = [
+            (Method::GET, "/files/a", "get"),
+            (Method::DELETE, "/files/a", "any"),
+            (propfind.clone(), "/files/a", "any"),
+            (propfind, "/dav", "dav"),
+        ];
+
+        for (method, path, expected) in tests.iter() {
+            let req = Request::builder()
+                .method(method)
+                .uri(*path)
+                .body(Body::empty())
+                .unwrap();
+            let res = router.serve(req).await.unwrap();
+            let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            assert_eq!(body, *expected);
+        }
+
+        assert_eq!(router.allowed("/dav"), ["PROPFIND", "PUT", "OPTIONS"]);
+        assert_eq!(
+            router.allowed("/files/a"),
+            ["CONNECT", "DELETE", "GET", "HEAD", "PATCH", "POST", "PUT", "TRACE", "OPTIONS"]
+        );
+
+        let req = Request::get("/dav").body(Body::empty()).unwrap();
+        let res = router.serve(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()[header::ALLOW], "PROPFIND, PUT, OPTIONS");
+
+        // `OPTIONS` requests are answered automatically, unless an `OPTIONS` route is
+        // registered
+        let nested = Router::default().get("/", echo("nested"));
+        let router = router
+            .options("/files/readme", echo("options"))
+            .any("/nested/*path", echo("any"))
+            .nest("/api", nested);
+        let options = |router: &Router, path: &str| {
+            router.serve(Request::options(path).body(Body::empty()).unwrap())
+        };
+
+        let res = options(&router, "/files/a").await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers()[header::ALLOW],
+            "CONNECT, DELETE, GET, HEAD, PATCH, POST, PUT, TRACE, OPTIONS"
+        );
+
+        let res = options(&router, "/files/readme").await.unwrap();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "options");
+
+        // nested routers answer with their own methods
+        let res = options(&router, "/api/").await.unwrap();
+        assert_eq!(res.headers()[header::ALLOW], "GET, HEAD, OPTIONS");
+
+        let router = router.global_options(echo("global"));
+        let res = options(&router, "/nested/a").await.unwrap();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "global");
+    }
+
+    #[test]
+    fn routes_include_nested_routers() {
+        let admin = Router::default().get("/users/:id", handler()).handle_named(
+            "stats",
+            "/stats",
+            Method::GET,
+            handler(),
+        );
+
+        let router = Router::default()
+            .any("/health", handler())
+            .nest("/admin", admin)
+            .nest_service("/legacy", handler());
+
+        let routes = router
+            .routes()
+            .map(|route| {
+                (
+                    route.method().cloned(),
+                    route.path().to_owned(),
+                    route.name().map(ToOwned::to_owned),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            routes,
+            [
+                (
+                    Some(Method::GET),
+                    "/admin/stats".to_owned(),
+                    Some("stats".to_owned())
+                ),
+                (Some(Method::GET), "/admin/users/:id".to_owned(), None),
+                (None, "/health".to_owned(), None),
+                (None, "/legacy/*".to_owned(), None),
+            ]
+        );
+
+        let mounts = router
+            .routes()
+            .filter(Route::is_mount)
+            .map(|route| route.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(mounts, ["* /legacy/*"]);
+    }
+
+    #[test]
+    fn url_for() {
+        let mut router = Router::default()
+            .handle_named("user", "/users/:id<u64>", Method::GET, handler())
+            .handle_named("files", "/files/*path", Method::GET, handler());
+
+        assert_eq!(
+            router.url_for("user", &[("id", "42")]).unwrap(),
+            "/users/42"
+        );
+        assert_eq!(
+            router.url_for("missing", &[]).err(),
+            Some(UrlError::UnknownRoute {
+                name: "missing".into()
+            })
+        );
+
+        // catch-all values keep their slashes, with or without a leading one
+        for path in ["docs/read me?.md", "/docs/read me?.md"] {
+            assert_eq!(
+                router.url_for("files", &[("path", path)]).unwrap(),
+                "/files/docs/read%20me%3F.md"
+            );
+        }
+
+        assert_eq!(
+            router
+                .try_handle_named("user", "/people/:id", Method::GET, handler())
+                .err(),
+            Some(RouteError::DuplicateName {
+                name: "user".into(),
+                path: "/people/:id".into()
+            })
+        );
+
+        // names of nested routers include the prefix, without its constraints
+        let admin = Router::default()
+            .handle_named("stats", "/stats/:day", Method::GET, handler())
+            .nest(
+                "/billing",
+                Router::default().handle_named("invoice", "/invoices/:id", Method::GET, handler()),
+            );
+        router
+            .try_nest("/tenants/:tenant<u64>/admin", admin)
+            .unwrap();
+
+        assert_eq!(
+            router
+                .url_for("stats", &[("tenant", "7"), ("day", "mon")])
+                .unwrap(),
+            "/tenants/7/admin/stats/mon"
+        );
+        assert_eq!(
+            router
+                .url_for("invoice", &[("tenant", "7"), ("id", "1")])
+                .unwrap(),
+            "/tenants/7/admin/billing/invoices/1"
+        );
+
+        let clash = Router::default().handle_named("user", "/users/:id", Method::GET, handler());
+        assert_eq!(
+            router.try_nest("/v2", clash).err(),
+            Some(RouteError::DuplicateName {
+                name: "user".into(),
+                path: "/v2/users/:id".into()
+            })
+        );
+        assert!(router
+            .routes()
+            .all(|route| !route.path().starts_with("/v2")));
+    }
+
+    #[test]
+    #[should_panic(expected = "a handler is already registered for GET '/'")]
+    fn handle_panics_on_error() {
+        Router::default().get("/", handler()).get("/", handler());
+    }
+}